@@ -0,0 +1,159 @@
+#[macro_use]
+extern crate criterion;
+extern crate life_struggle;
+
+use criterion::{BenchmarkId, Criterion};
+use life_struggle::{BitTile, Board, ByteTile, LifeTile, VecBoard, VecTile};
+
+fn lwss_at<T: LifeTile>(t: &mut T, x: usize, y: usize) {
+    let mut q = |xx: usize, yy: usize| t.set(x + xx, y + yy, true);
+
+    // Light weight space ship going +x
+    q(0, 0);
+    q(0, 2);
+    q(1, 3);
+    q(2, 3);
+    q(3, 3);
+    q(4, 3);
+    q(4, 2);
+    q(4, 1);
+    q(3, 0);
+}
+
+// A tile packed with a grid of lwss, one every 10 cells, or a single lwss if smaller.
+fn lwss_grid<T: LifeTile>(size: usize) -> T {
+    let mut a = T::new(size);
+    let count = (size / 10).max(1);
+    for x in 0..count {
+        for y in 0..count {
+            lwss_at(&mut a, x * 10, y * 10);
+        }
+    }
+    a
+}
+
+// Two gliders going -x+y, as in game::tests::test_lwss_vs_gliders
+fn gliders<T: LifeTile>(size: usize) -> T {
+    let mut b = T::new(size);
+    {
+        let mut q = |x: usize, y: usize| b.set(x, y, true);
+        q(9, 0);
+        q(8, 1);
+        q(10, 2);
+        q(9, 2);
+        q(8, 2);
+
+        q(9, 10);
+        q(8, 11);
+        q(10, 12);
+        q(9, 12);
+        q(8, 12);
+    }
+    b
+}
+
+fn board<T: LifeTile>(size: usize, generations: usize, incremental: bool) -> VecBoard<T> {
+    let mut a = T::new(size);
+    lwss_at(&mut a, 0, 0);
+    let b = gliders::<T>(size);
+    let mut board = if incremental {
+        let period = VecBoard::quiescence_period(&a, &b, 64);
+        VecBoard::new_incremental(a, b, period).unwrap()
+    } else {
+        VecBoard::new(a, b).unwrap()
+    };
+    for _ in 0..generations {
+        board = board.next_generation().unwrap();
+    }
+    board
+}
+
+// Like board, but stepping in place with Board::step.
+fn board_in_place<T: LifeTile>(size: usize, generations: usize, incremental: bool) -> VecBoard<T> {
+    let mut a = T::new(size);
+    lwss_at(&mut a, 0, 0);
+    let b = gliders::<T>(size);
+    let mut board = if incremental {
+        let period = VecBoard::quiescence_period(&a, &b, 64);
+        VecBoard::new_incremental(a, b, period).unwrap()
+    } else {
+        VecBoard::new(a, b).unwrap()
+    };
+    for _ in 0..generations {
+        assert!(board.step());
+    }
+    board
+}
+
+fn tile_evolution<T: LifeTile>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("tile_evolution/{}", name));
+    // From tournament sized tiles up, as Engine::auto picks an engine by size.
+    for size in [8, 40, 200, 2000].iter() {
+        if *size > 200 {
+            group.sample_size(10);
+        }
+        let a = lwss_grid::<T>(*size);
+        group.bench_with_input(BenchmarkId::new("lwss", size), &a, |bench, a| {
+            bench.iter(|| a.next_generation(a, a))
+        });
+    }
+    group.finish();
+}
+
+fn board_step<T: LifeTile>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("board_step/{}", name));
+    for incremental in [false, true].iter() {
+        // A board part way through a match, so there is a contested region to update.
+        let board = board::<T>(40, 500, *incremental);
+        group.bench_function(mode(*incremental), |bench| {
+            bench.iter(|| board.next_generation())
+        });
+    }
+    group.finish();
+}
+
+fn full_match<T: LifeTile>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("full_match/{}", name));
+    group.sample_size(10);
+    for incremental in [false, true].iter() {
+        group.bench_function(mode(*incremental), |bench| {
+            bench.iter(|| board::<T>(40, 500, *incremental))
+        });
+    }
+    for incremental in [false, true].iter() {
+        let name = format!("{}_in_place", mode(*incremental));
+        group.bench_function(name, |bench| {
+            bench.iter(|| board_in_place::<T>(40, 500, *incremental))
+        });
+    }
+    group.finish();
+}
+
+fn mode(incremental: bool) -> &'static str {
+    if incremental {
+        "incremental"
+    } else {
+        "full"
+    }
+}
+
+fn vec_tile(c: &mut Criterion) {
+    tile_evolution::<VecTile>(c, "VecTile");
+    board_step::<VecTile>(c, "VecTile");
+    full_match::<VecTile>(c, "VecTile");
+}
+
+fn bit_tile(c: &mut Criterion) {
+    tile_evolution::<BitTile>(c, "BitTile");
+    board_step::<BitTile>(c, "BitTile");
+    full_match::<BitTile>(c, "BitTile");
+}
+
+fn byte_tile(c: &mut Criterion) {
+    tile_evolution::<ByteTile>(c, "ByteTile");
+    board_step::<ByteTile>(c, "ByteTile");
+    full_match::<ByteTile>(c, "ByteTile");
+}
+
+criterion_group!(benches, vec_tile, bit_tile, byte_tile);
+criterion_main!(benches);
@@ -0,0 +1,170 @@
+//! Finite arena boards, bounded by walls, mirrors or a ring instead of extending forever.
+
+use board::save_image;
+use rayon::prelude::*;
+use std::path::Path;
+use tile::*;
+
+/// What lies beyond the far edges of an arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Permanently dead cells.
+    Wall,
+    /// The edge tile reflected, as if the world were mirrored at the edge.
+    Mirror,
+    /// a's far edge joins b's far edge, making the arena a ring.
+    Ring,
+}
+
+/// Life Struggle in a finite arena:
+/// like Board, a's tiles are to the -x, and b's (mirrored) tiles to the +x of x=0,
+/// but each player only has `per_player` tiles, bounded by `edge`.
+/// With no infinite reinforcements from the background, matches play out to a decided outcome.
+/// The arena covers x in -per_player..per_player.
+#[derive(Debug, Clone)]
+pub struct ArenaBoard<T>
+where
+    T: LifeTile,
+{
+    // Each player's tile, evolved alone, for scoring.
+    a: T,
+    b: T,
+    per_player: usize,
+    edge: Edge,
+    // Empty tile, for Edge::Wall
+    wall: T,
+    tiles: Vec<T>,
+}
+
+impl<T> ArenaBoard<T>
+where
+    T: LifeTile,
+{
+    /// Fill each player's half of the arena with their tile. b is expected to be mirrored already.
+    pub fn new(a: T, b: T, per_player: usize, edge: Edge) -> ArenaBoard<T> {
+        assert!(a.size() == b.size());
+        assert!(per_player > 0);
+
+        let mut tiles = vec![a.clone(); per_player];
+        tiles.extend(vec![b.clone(); per_player]);
+        let wall = T::new(a.size());
+        ArenaBoard {
+            a,
+            b,
+            per_player,
+            edge,
+            wall,
+            tiles,
+        }
+    }
+
+    /// The board one generation later.
+    pub fn next_generation(&self) -> ArenaBoard<T> {
+        let (a_next, b_next): (T, T) = rayon::join(
+            || self.a.next_generation(&self.a, &self.a),
+            || self.b.next_generation(&self.b, &self.b),
+        );
+
+        let last = self.tiles.len() - 1;
+        let tiles = (0..self.tiles.len())
+            .into_par_iter()
+            .map(|i| -> T {
+                let t = &self.tiles[i];
+                let reflected;
+                let previous = if i == 0 {
+                    match self.edge {
+                        Edge::Wall => &self.wall,
+                        Edge::Mirror => {
+                            reflected = t.mirror();
+                            &reflected
+                        }
+                        Edge::Ring => &self.tiles[last],
+                    }
+                } else {
+                    &self.tiles[i - 1]
+                };
+                let reflected;
+                let next = if i == last {
+                    match self.edge {
+                        Edge::Wall => &self.wall,
+                        Edge::Mirror => {
+                            reflected = t.mirror();
+                            &reflected
+                        }
+                        Edge::Ring => &self.tiles[0],
+                    }
+                } else {
+                    &self.tiles[i + 1]
+                };
+                t.next_generation(previous, next)
+            }).collect();
+
+        ArenaBoard {
+            a: a_next,
+            b: b_next,
+            per_player: self.per_player,
+            edge: self.edge,
+            wall: self.wall.clone(),
+            tiles,
+        }
+    }
+
+    /// Same rule as Board::score, limited to the arena:
+    /// 1 point added for each tile of enemy territory converted into your tile,
+    /// 1 point deducted for each tile of your territory disrupted.
+    pub fn score(&self) -> (isize, isize) {
+        let mut score_a = 0;
+        let mut score_b = 0;
+        for x in self.first()..self.end() {
+            let t = self.tile_at(x);
+            if x < 0 {
+                if t != &self.a {
+                    score_a -= 1;
+                    if t == &self.b {
+                        score_b += 1;
+                    }
+                }
+            } else if t != &self.b {
+                score_b -= 1;
+                if t == &self.a {
+                    score_a += 1;
+                }
+            }
+        }
+        (score_a, score_b)
+    }
+
+    /// a's tile evolved alone to the current generation, which a's territory is scored against.
+    pub fn a_current(&self) -> &T {
+        &self.a
+    }
+
+    /// b's tile evolved alone to the current generation, which b's territory is scored against.
+    pub fn b_current(&self) -> &T {
+        &self.b
+    }
+
+    /// Lowest x in the arena.
+    pub fn first(&self) -> isize {
+        -(self.per_player as isize)
+    }
+
+    /// One past the highest x in the arena.
+    pub fn end(&self) -> isize {
+        self.per_player as isize
+    }
+
+    /// x must be in first()..end()
+    pub fn tile_at(&self, x: isize) -> &T {
+        &self.tiles[(x - self.first()) as usize]
+    }
+
+    /// Save the whole arena as an image, one tile after another.
+    pub fn print_image<Q>(&self, path: Q)
+    where
+        Q: AsRef<Path>,
+    {
+        let tiles: Vec<&T> = self.tiles.iter().collect();
+        save_image(&tiles, path);
+    }
+}
@@ -1,14 +1,26 @@
+//! The `Board` trait for an endless row of tiles contested by two players,
+//! and `VecBoard`, its main implementation.
+
 use image;
+use num_integer::Integer;
 use rayon;
 use rayon::prelude::*;
+use score::*;
+use snapshot::Snapshot;
+use std::collections::VecDeque;
 use std::marker::Sized;
 use std::path::Path;
+use std::sync::Arc;
 use tile::*;
 
+/// The 2 player Life Struggle world: player a's tiles fill x < 0 and player b's fill x >= 0,
+/// except for a finite contested region in between, where tiles can be anything.
+/// Tiles are indexed by their x position; every row along y is the same.
 pub trait Board<T>: Sized
 where
     T: LifeTile,
 {
+    /// None if a and b are the same, since nothing can happen (a convergence draw).
     fn new(a: T, b: T) -> Option<Self> {
         assert!(a.size() == b.size());
 
@@ -19,38 +31,55 @@ where
         Some(Self::new_inner(a, b))
     }
 
+    /// Construct a board for different a and b. Use new instead, which checks for a convergence draw.
     fn new_inner(a: T, b: T) -> Self;
+    /// Lowest x of the contested region: all tiles below it match a_current.
     fn lowest_non_a(&self) -> isize;
+    /// Highest x of the contested region: all tiles above it match b_current.
     fn highest_non_b(&self) -> isize;
+    /// None if a and b have converged to the same tile (a convergence draw).
     fn next_generation(&self) -> Option<Self>;
+
+    /// Advance self by one generation in place.
+    /// Returns false, leaving self unchanged, if a and b converge (a convergence draw).
+    fn step(&mut self) -> bool {
+        match self.next_generation() {
+            Some(b) => {
+                *self = b;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Player a's tile evolved alone, which the background to the -x matches.
     fn a_current(&self) -> &T;
+    /// Player b's tile evolved alone, which the background to the +x matches.
     fn b_current(&self) -> &T;
 
-    fn score(&self) -> (isize, isize) {
-        let first = self.lowest_non_a();
-        let last = self.highest_non_b();
-
-        // init to contigious tiles captured (negative is tiles were lost)
-        let mut score_a = first;
-        let mut score_b = -last - 1;
+    /// Number of tiles in the contested region.
+    fn contested_width(&self) -> usize {
+        (self.highest_non_b() - self.lowest_non_a() + 1).max(0) as usize
+    }
 
-        // Go through contested area, and see if any tiles match a or b
-        for x in first..=last {
-            let t = self.tile_at(x);
-            if t == self.a_current() {
-                score_a += 1;
-            } else if t == self.b_current() {
-                score_b += 1;
-            }
-        }
+    /// Score using the rule from the README: see CaptureScorer.
+    fn score(&self) -> (isize, isize) {
+        CaptureScorer.score(self)
+    }
 
-        return (score_a, score_b);
+    /// Score using any of the rules in the score module.
+    fn score_with<S>(&self, scorer: &S) -> (isize, isize)
+    where
+        S: Scorer,
+    {
+        scorer.score(self)
     }
 
+    /// Width and height of every tile, in cells.
     fn tile_size(&self) -> usize {
         self.a_current().size()
     }
 
+    /// Print the contested region and a tile either side of it to stdout, as text.
     fn print(&self) {
         let first = self.lowest_non_a() - 1;
         let last = self.highest_non_b() + 1;
@@ -67,43 +96,45 @@ where
         }
     }
 
+    /// Save the contested region and a tile either side of it as an image (see save_image).
     fn print_image<Q>(&self, path: Q)
     where
         Q: AsRef<Path>,
     {
         let first = self.lowest_non_a() - 1;
         let last = self.highest_non_b() + 1;
-        let range = first..=last;
-        let tile_size = self.tile_size();
-        let mut imgbuf =
-            image::GrayImage::new((tile_size * range.count()) as u32, tile_size as u32);
+        let tiles: Vec<&T> = (first..=last).map(|x| self.tile_at(x)).collect();
+        save_image(&tiles, path);
+    }
 
-        //for x in range {
-        //    img.put_pixel(x * tile_size, tile_size, image::Luma([255 as u8]))
-        //    img.put_pixel((x +1) * tile_size - 1, tile_size, image::Luma([255 as u8]))
-        //}
+    /// The tile at x, which can be anywhere on the infinite line of tiles.
+    fn tile_at(&self, x: isize) -> &T;
+}
 
-        for x in first..=last {
-            let t = self.tile_at(x);
-            for yy in 0usize..self.tile_size() {
-                for xx in 0usize..self.tile_size() {
-                    let b = t.get(xx, yy);
-                    let luma: u8 = if b { 0 } else { 255 };
-                    imgbuf.put_pixel(
-                        ((x - first) as usize * tile_size + xx) as u32,
-                        yy as u32,
-                        image::Luma([luma]),
-                    )
-                }
+/// Save a row of tiles as an image, one pixel per cell, live cells in black.
+pub fn save_image<T, Q>(tiles: &[&T], path: Q)
+where
+    T: LifeTile,
+    Q: AsRef<Path>,
+{
+    let tile_size = tiles.first().map_or(0, |t| t.size());
+    let mut imgbuf = image::GrayImage::new((tile_size * tiles.len()) as u32, tile_size as u32);
+
+    for (x, t) in tiles.iter().enumerate() {
+        for yy in 0usize..tile_size {
+            for xx in 0usize..tile_size {
+                let b = t.get(xx, yy);
+                let luma: u8 = if b { 0 } else { 255 };
+                imgbuf.put_pixel((x * tile_size + xx) as u32, yy as u32, image::Luma([luma]))
             }
         }
-
-        imgbuf.save(path).unwrap();
     }
 
-    fn tile_at(&self, x: isize) -> &T;
+    imgbuf.save(path).unwrap();
 }
 
+/// Board storing the contested region as a Vec of tiles,
+/// with the backgrounds represented by a single tile each.
 #[derive(Debug)]
 pub struct VecBoard<T>
 where
@@ -114,6 +145,63 @@ where
     num_a_at_start: isize,
     a: T,
     b: T,
+    // None to recompute every tile every generation.
+    quiescence: Option<Quiescence<T>>,
+    // Tiles from earlier generations for step to overwrite, so it doesn't allocate.
+    spare: StepBuffers<T>,
+}
+
+#[derive(Debug)]
+struct StepBuffers<T> {
+    // The previous generation's tiles, to become the next generation's.
+    tiles: Vec<T>,
+    // Other unused tiles.
+    pool: Vec<T>,
+}
+
+impl<T> Default for StepBuffers<T> {
+    fn default() -> Self {
+        StepBuffers {
+            tiles: vec![],
+            pool: vec![],
+        }
+    }
+}
+
+impl<T> StepBuffers<T>
+where
+    T: LifeTile,
+{
+    fn take_tile(&mut self, size: usize) -> T {
+        self.pool.pop().unwrap_or_else(|| T::new(size))
+    }
+}
+
+// Tracks which tiles are quiescent: the same as they were `period` generations ago.
+// A tile whose whole neighborhood is quiescent will also be the same next generation
+// as it was `period` generations before that, so it can be copied instead of recomputed.
+// This makes wide contested regions full of still lifes or oscillators
+// (in phase with period) cheap.
+#[derive(Debug)]
+struct Quiescence<T>
+where
+    T: LifeTile,
+{
+    period: usize,
+    // The boards 1, 2, ... (period - 1) generations ago, most recent first.
+    // These don't have quiescence of their own. step moves each generation in as it is replaced,
+    // while next_generation has to copy the board it is called on.
+    history: VecDeque<Arc<VecBoard<T>>>,
+    // For each tile in tiles: does it differ from `period` generations ago?
+    changed: Vec<bool>,
+    // The previous generation's changed, for step to overwrite.
+    spare_changed: Vec<bool>,
+    // For tiles outside of tiles, which are a or b now:
+    // did the background change, and what range was not background `period` generations ago?
+    a_changed: bool,
+    b_changed: bool,
+    old_lowest_non_a: isize,
+    old_highest_non_b: isize,
 }
 
 impl<T> Board<T> for VecBoard<T>
@@ -141,47 +229,235 @@ where
             return None;
         }
 
-        let mut tiles_new: Vec<T> = (first..last + 1)
-            .into_par_iter()
-            .map(|x| -> T {
-                self.tile_at(x)
-                    .next_generation(self.tile_at(x - 1), self.tile_at(x + 1))
-            }).collect();
-
-        let mut num_a_at_start_new = 0;
-        for i in 0..tiles_new.len() {
-            if tiles_new[i] == a_next {
-                num_a_at_start_new = i as isize + 1;
+        // The board the next generation is compared to, `period` generations before it,
+        // or None if every tile must be recomputed.
+        let q = self.quiescence.as_ref();
+        let reference: Option<&VecBoard<T>> = q.and_then(|q| {
+            if q.period == 1 {
+                Some(self)
             } else {
-                break;
+                q.history.get(q.period - 2).map(|b| &**b)
             }
-        }
+        });
 
-        while tiles_new.len() > 0 && tiles_new.last().unwrap() == &b_next {
+        let (mut tiles_new, mut changed_new): (Vec<T>, Vec<bool>) = (first..last + 1)
+            .into_par_iter()
+            .map(|x| -> (T, bool) {
+                if let (Some(q), Some(r)) = (q, reference) {
+                    if !(q.changed_at(self, x - 1)
+                        || q.changed_at(self, x)
+                        || q.changed_at(self, x + 1))
+                    {
+                        return (r.tile_at(x).clone(), false);
+                    }
+                }
+                let t = self
+                    .tile_at(x)
+                    .next_generation(self.tile_at(x - 1), self.tile_at(x + 1));
+                let changed = reference.is_none_or(|r| r.tile_at(x) != &t);
+                (t, changed)
+            })
+            .unzip();
+
+        let num_a_at_start_new = tiles_new.iter().take_while(|t| **t == a_next).count() as isize;
+
+        while !tiles_new.is_empty() && tiles_new.last().unwrap() == &b_next {
             tiles_new.pop();
+            changed_new.pop();
         }
 
+        let quiescence = q.map(|q| {
+            let mut history = VecDeque::with_capacity(q.period);
+            if q.period > 1 {
+                history.push_back(Arc::new(VecBoard {
+                    a: self.a.clone(),
+                    b: self.b.clone(),
+                    tiles: self.tiles.clone(),
+                    num_a_at_start: self.num_a_at_start,
+                    vec_start: self.vec_start,
+                    quiescence: None,
+                    spare: StepBuffers::default(),
+                }));
+                history.extend(q.history.iter().take(q.period - 2).cloned());
+            }
+            Quiescence {
+                period: q.period,
+                history,
+                changed: changed_new,
+                spare_changed: vec![],
+                a_changed: reference.is_none_or(|r| r.a != a_next),
+                b_changed: reference.is_none_or(|r| r.b != b_next),
+                old_lowest_non_a: reference.map_or(0, |r| r.lowest_non_a()),
+                old_highest_non_b: reference.map_or(0, |r| r.highest_non_b()),
+            }
+        });
+
         let b_new = VecBoard {
             a: a_next,
             b: b_next,
             tiles: tiles_new,
             num_a_at_start: num_a_at_start_new,
             vec_start: first,
+            quiescence,
+            spare: StepBuffers::default(),
         };
 
         debug_assert!(b_new.tile_at(b_new.lowest_non_a()) != &b_new.a);
         debug_assert!(b_new.tile_at(b_new.highest_non_b()) != &b_new.b);
 
-        return Some(b_new);
+        Some(b_new)
+    }
+
+    fn step(&mut self) -> bool {
+        let first = self.lowest_non_a() - 1;
+        let last = self.highest_non_b() + 1;
+        let size = self.tile_size();
+
+        let mut a_next = self.spare.take_tile(size);
+        let mut b_next = self.spare.take_tile(size);
+        rayon::join(
+            || self.a.next_generation_into(&self.a, &self.a, &mut a_next),
+            || self.b.next_generation_into(&self.b, &self.b, &mut b_next),
+        );
+
+        if a_next == b_next {
+            self.spare.pool.push(a_next);
+            self.spare.pool.push(b_next);
+            return false;
+        }
+
+        let len = (last - first + 1) as usize;
+        let mut tiles_new = std::mem::take(&mut self.spare.tiles);
+        while tiles_new.len() > len {
+            self.spare.pool.push(tiles_new.pop().unwrap());
+        }
+        while tiles_new.len() < len {
+            tiles_new.push(self.spare.take_tile(size));
+        }
+        let mut changed_new = self.quiescence.as_mut().map(|q| {
+            let mut changed = std::mem::take(&mut q.spare_changed);
+            changed.clear();
+            changed.resize(len, false);
+            changed
+        });
+
+        let update = {
+            let this = &*self;
+            let q = this.quiescence.as_ref();
+            // As in next_generation, but the history holds the generations before self.
+            let reference: Option<&VecBoard<T>> = q.and_then(|q| {
+                if q.period == 1 {
+                    Some(this)
+                } else {
+                    q.history.get(q.period - 2).map(|b| &**b)
+                }
+            });
+
+            let compute = |t: &mut T, x: isize| -> bool {
+                if let (Some(q), Some(r)) = (q, reference) {
+                    if !(q.changed_at(this, x - 1)
+                        || q.changed_at(this, x)
+                        || q.changed_at(this, x + 1))
+                    {
+                        t.clone_from(r.tile_at(x));
+                        return false;
+                    }
+                }
+                this.tile_at(x)
+                    .next_generation_into(this.tile_at(x - 1), this.tile_at(x + 1), t);
+                reference.is_none_or(|r| r.tile_at(x) != t)
+            };
+            match changed_new.as_mut() {
+                Some(changed_new) => tiles_new
+                    .par_iter_mut()
+                    .zip(changed_new.par_iter_mut())
+                    .zip(first..last + 1)
+                    .for_each(|((t, changed), x)| *changed = compute(t, x)),
+                None => tiles_new
+                    .par_iter_mut()
+                    .zip(first..last + 1)
+                    .for_each(|(t, x)| {
+                        compute(t, x);
+                    }),
+            }
+
+            q.map(|_| {
+                (
+                    reference.is_none_or(|r| r.a != a_next),
+                    reference.is_none_or(|r| r.b != b_next),
+                    reference.map_or(0, |r| r.lowest_non_a()),
+                    reference.map_or(0, |r| r.highest_non_b()),
+                )
+            })
+        };
+
+        let num_a_at_start_new = tiles_new.iter().take_while(|t| **t == a_next).count() as isize;
+
+        while !tiles_new.is_empty() && tiles_new.last().unwrap() == &b_next {
+            self.spare.pool.push(tiles_new.pop().unwrap());
+            if let Some(changed_new) = changed_new.as_mut() {
+                changed_new.pop();
+            }
+        }
+
+        let a_old = std::mem::replace(&mut self.a, a_next);
+        let b_old = std::mem::replace(&mut self.b, b_next);
+        let tiles_old = std::mem::replace(&mut self.tiles, tiles_new);
+        let (num_a_at_start_old, vec_start_old) = (self.num_a_at_start, self.vec_start);
+        self.num_a_at_start = num_a_at_start_new;
+        self.vec_start = first;
+
+        debug_assert!(self.tile_at(self.lowest_non_a()) != &self.a);
+        debug_assert!(self.tile_at(self.highest_non_b()) != &self.b);
+
+        if let (
+            Some(q),
+            Some(changed_new),
+            Some((a_changed, b_changed, old_lowest_non_a, old_highest_non_b)),
+        ) = (self.quiescence.as_mut(), changed_new, update)
+        {
+            q.spare_changed = std::mem::replace(&mut q.changed, changed_new);
+            q.a_changed = a_changed;
+            q.b_changed = b_changed;
+            q.old_lowest_non_a = old_lowest_non_a;
+            q.old_highest_non_b = old_highest_non_b;
+            if q.period > 1 {
+                // Keep the generation just replaced for later ones to compare to, moving it
+                // rather than copying, and reuse the tiles of the one which is now too old.
+                q.history.push_front(Arc::new(VecBoard {
+                    a: a_old,
+                    b: b_old,
+                    tiles: tiles_old,
+                    num_a_at_start: num_a_at_start_old,
+                    vec_start: vec_start_old,
+                    quiescence: None,
+                    spare: StepBuffers::default(),
+                }));
+                if q.history.len() > q.period - 1 {
+                    if let Ok(old) = Arc::try_unwrap(q.history.pop_back().unwrap()) {
+                        self.spare.pool.extend(old.tiles);
+                        self.spare.pool.push(old.a);
+                        self.spare.pool.push(old.b);
+                    }
+                }
+                return true;
+            }
+        }
+        self.spare.pool.push(a_old);
+        self.spare.pool.push(b_old);
+        self.spare.tiles = tiles_old;
+        true
     }
 
     fn new_inner(a: T, b: T) -> Self {
         VecBoard {
-            a: a,
-            b: b,
+            a,
+            b,
             tiles: vec![],
             num_a_at_start: 0,
             vec_start: 0,
+            quiescence: None,
+            spare: StepBuffers::default(),
         }
     }
 
@@ -203,3 +479,166 @@ where
         }
     }
 }
+
+impl<T> VecBoard<T>
+where
+    T: LifeTile,
+{
+    /// The exact state of the board, for checkpointing.
+    pub fn snapshot(&self, generation: usize) -> Snapshot<T> {
+        Snapshot {
+            generation,
+            vec_start: self.vec_start,
+            num_a_at_start: self.num_a_at_start,
+            a: self.a.clone(),
+            b: self.b.clone(),
+            tiles: self.tiles.clone(),
+        }
+    }
+
+    /// Returns the board, and the generation it was at.
+    pub fn from_snapshot(snapshot: Snapshot<T>) -> (VecBoard<T>, usize) {
+        let board = VecBoard {
+            a: snapshot.a,
+            b: snapshot.b,
+            tiles: snapshot.tiles,
+            num_a_at_start: snapshot.num_a_at_start,
+            vec_start: snapshot.vec_start,
+            quiescence: None,
+            spare: StepBuffers::default(),
+        };
+        (board, snapshot.generation)
+    }
+}
+
+impl<T> VecBoard<T>
+where
+    T: LifeTile,
+{
+    /// Like Board::new, but only recomputes tiles which may have changed:
+    /// tiles are skipped while their neighborhood is the same as `period` generations ago.
+    /// The result is identical either way, but a period matching the backgrounds' cycles
+    /// (see quiescence_period) lets oscillating debris be skipped too.
+    pub fn new_incremental(a: T, b: T, period: usize) -> Option<VecBoard<T>> {
+        assert!(period > 0);
+        VecBoard::new(a, b).map(|mut board| {
+            board.quiescence = Some(Quiescence {
+                period,
+                history: VecDeque::new(),
+                changed: vec![],
+                spare_changed: vec![],
+                a_changed: true,
+                b_changed: true,
+                old_lowest_non_a: 0,
+                old_highest_non_b: 0,
+            });
+            board
+        })
+    }
+
+    /// A good period for new_incremental: the shortest which both a and b repeat over,
+    /// if they both cycle from the start and it is at most max_period, otherwise 1.
+    pub fn quiescence_period(a: &T, b: &T, max_period: usize) -> usize {
+        match (a.self_period(max_period), b.self_period(max_period)) {
+            (Some(period_a), Some(period_b)) if period_a.lcm(&period_b) <= max_period => {
+                period_a.lcm(&period_b)
+            }
+            _ => 1,
+        }
+    }
+}
+
+impl<T> Quiescence<T>
+where
+    T: LifeTile,
+{
+    // Has the tile at x on board changed since `period` generations ago?
+    fn changed_at(&self, board: &VecBoard<T>, x: isize) -> bool {
+        if x < board.vec_start {
+            self.a_changed || x >= self.old_lowest_non_a
+        } else if x > board.highest_non_b() {
+            self.b_changed || x <= self.old_highest_non_b
+        } else {
+            self.changed[(x - board.vec_start) as usize]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generate::Generator;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Incremental boards must match full recomputation exactly, every generation,
+    // whether made by next_generation or stepped in place.
+    fn check_incremental(a: VecTile, b: VecTile, period: usize, generations: usize) {
+        let mut full = VecBoard::new(a.clone(), b.clone());
+        let mut incremental = VecBoard::new_incremental(a.clone(), b.clone(), period);
+        let mut stepped = VecBoard::new_incremental(a, b, period);
+        for g in 0..generations {
+            match (full, incremental, stepped) {
+                (Some(f), Some(i), Some(mut s)) => {
+                    assert_eq!(f.snapshot(g), i.snapshot(g));
+                    assert_eq!(f.snapshot(g), s.snapshot(g));
+                    full = f.next_generation();
+                    incremental = i.next_generation();
+                    stepped = if s.step() { Some(s) } else { None };
+                }
+                (f, i, s) => {
+                    assert!(f.is_none() && i.is_none() && s.is_none());
+                    return;
+                }
+            }
+        }
+    }
+
+    // Stepping in place must match next_generation exactly, while buffers are reused.
+    #[test]
+    fn test_step_matches_next_generation() {
+        let mut rng = StdRng::from_seed([6; 32]);
+        let random = Generator::Uniform { density: 0.3 };
+        for _ in 0..10 {
+            let a: VecTile = random.generate(8, &mut rng);
+            let b: VecTile = random.generate(8, &mut rng);
+            let mut full = VecBoard::new(a.clone(), b.clone());
+            let mut stepped = VecBoard::new(a, b);
+            for g in 0..300 {
+                match (full, stepped) {
+                    (Some(f), Some(mut s)) => {
+                        assert_eq!(f.snapshot(g), s.snapshot(g));
+                        full = f.next_generation();
+                        stepped = if s.step() { Some(s) } else { None };
+                    }
+                    (f, s) => {
+                        assert!(f.is_none() && s.is_none());
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_matches_full() {
+        let mut rng = StdRng::from_seed([5; 32]);
+        let random = Generator::Uniform { density: 0.3 };
+        for _ in 0..10 {
+            let a: VecTile = random.generate(8, &mut rng);
+            let b: VecTile = random.generate(8, &mut rng);
+            for period in [1, 2, 6].iter() {
+                check_incremental(a.clone(), b.clone(), *period, 300);
+            }
+        }
+
+        // Oscillating backgrounds: a blinker against empty space.
+        let mut a = VecTile::new(8);
+        a.set(3, 2, true);
+        a.set(3, 3, true);
+        a.set(3, 4, true);
+        let b: VecTile = Generator::Uniform { density: 0.3 }.generate(8, &mut rng);
+        assert_eq!(VecBoard::quiescence_period(&a, &a, 64), 2);
+        check_incremental(a, b, 2, 300);
+    }
+}
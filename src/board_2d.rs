@@ -0,0 +1,225 @@
+//! Boards where the players' territories divide the plane in two dimensions,
+//! such as into quadrants or along a diagonal.
+
+use image;
+use multi_board::capture_scores;
+use rayon::prelude::*;
+use std::path::Path;
+use tile::*;
+
+/// How the plane is split into territories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout2D {
+    /// Square regions meeting at a corner.
+    /// With 4 players, each gets one quadrant.
+    /// With 2 players, each gets two diagonally opposite quadrants (a checkerboard).
+    Quadrants,
+    /// 2 players, split into diagonal bands along x = y.
+    Diagonal,
+}
+
+/// Life Struggle in 2 dimensions.
+/// Unlike Board, rows of tiles along y can differ, so territories can have borders in any direction
+/// and tiles have to defend against attacks from more than one side.
+/// The layout repeats endlessly in both x and y, every `width` tiles,
+/// so only one period of it is simulated, wrapping around at the edges.
+#[derive(Debug, Clone)]
+pub struct Board2D<T>
+where
+    T: LifeTile,
+{
+    // Each player's tile, evolved alone.
+    players: Vec<T>,
+    width: usize,
+    // Which player owns each tile position, indexed like tiles.
+    owners: Vec<usize>,
+    tiles: Vec<T>,
+}
+
+impl<T> Board2D<T>
+where
+    T: LifeTile,
+{
+    /// Each region is region_width tiles across.
+    pub fn new(players: Vec<T>, layout: Layout2D, region_width: usize) -> Board2D<T> {
+        assert!(region_width > 0);
+        let size = players[0].size();
+        assert!(players.iter().all(|p| p.size() == size));
+
+        let width = region_width * 2;
+        let mut owners = Vec::with_capacity(width * width);
+        for y in 0..width {
+            for x in 0..width {
+                let owner = match layout {
+                    Layout2D::Quadrants => {
+                        let qx = x / region_width;
+                        let qy = y / region_width;
+                        match players.len() {
+                            2 => (qx + qy) % 2,
+                            4 => qx + qy * 2,
+                            _ => panic!("Quadrants layout requires 2 or 4 players"),
+                        }
+                    }
+                    Layout2D::Diagonal => {
+                        assert!(players.len() == 2, "Diagonal layout requires 2 players");
+                        ((x + width - y) % width) / region_width
+                    }
+                };
+                owners.push(owner);
+            }
+        }
+
+        let tiles = owners.iter().map(|&o| players[o].clone()).collect();
+        Board2D {
+            players,
+            width,
+            owners,
+            tiles,
+        }
+    }
+
+    /// The board one generation later.
+    pub fn next_generation(&self) -> Board2D<T> {
+        let players = self
+            .players
+            .par_iter()
+            .map(|p| p.next_generation(p, p))
+            .collect();
+
+        let width = self.width as isize;
+        let tiles = (0..self.tiles.len() as isize)
+            .into_par_iter()
+            .map(|i| {
+                let x = i % width;
+                let y = i / width;
+                let n = |dx: isize, dy: isize| self.tile_at(x + dx, y + dy);
+                let neighbors = [
+                    [n(-1, -1), n(0, -1), n(1, -1)],
+                    [n(-1, 0), n(0, 0), n(1, 0)],
+                    [n(-1, 1), n(0, 1), n(1, 1)],
+                ];
+                self.tile_at(x, y).next_generation_2d(&neighbors)
+            }).collect();
+
+        Board2D {
+            players,
+            width: self.width,
+            owners: self.owners.clone(),
+            tiles,
+        }
+    }
+
+    /// One score per player, see capture_scores.
+    pub fn scores(&self) -> Vec<isize> {
+        capture_scores(&self.players, &self.owners, &self.tiles)
+    }
+
+    /// The current tile of a player, evolved alone.
+    pub fn player_current(&self, player: usize) -> &T {
+        &self.players[player]
+    }
+
+    /// Number of tiles in x and y before the layout repeats.
+    pub fn period(&self) -> usize {
+        self.width
+    }
+
+    /// Index of the player whose territory the tile at (x, y) is in.
+    pub fn owner_at(&self, x: isize, y: isize) -> usize {
+        self.owners[self.index(x, y)]
+    }
+
+    /// The tile at (x, y), which can be anywhere on the infinite plane.
+    pub fn tile_at(&self, x: isize, y: isize) -> &T {
+        &self.tiles[self.index(x, y)]
+    }
+
+    /// Save one period of the board as an image, one pixel per cell, live cells in black.
+    pub fn print_image<Q>(&self, path: Q)
+    where
+        Q: AsRef<Path>,
+    {
+        let tile_size = self.players[0].size();
+        let pixels = (tile_size * self.width) as u32;
+        let mut imgbuf = image::GrayImage::new(pixels, pixels);
+
+        for y in 0..self.width {
+            for x in 0..self.width {
+                let t = self.tile_at(x as isize, y as isize);
+                for yy in 0..tile_size {
+                    for xx in 0..tile_size {
+                        let luma: u8 = if t.get(xx, yy) { 0 } else { 255 };
+                        imgbuf.put_pixel(
+                            (x * tile_size + xx) as u32,
+                            (y * tile_size + yy) as u32,
+                            image::Luma([luma]),
+                        )
+                    }
+                }
+            }
+        }
+
+        imgbuf.save(path).unwrap();
+    }
+
+    fn index(&self, x: isize, y: isize) -> usize {
+        let width = self.width as isize;
+        (x.rem_euclid(width) + y.rem_euclid(width) * width) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generate::Generator;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_next_generation_2d_matches_1d() {
+        let mut rng = StdRng::from_seed([7; 32]);
+        let random = Generator::Uniform { density: 0.5 };
+        for _ in 0..20 {
+            let previous: VecTile = random.generate(10, &mut rng);
+            let t: VecTile = random.generate(10, &mut rng);
+            let next: VecTile = random.generate(10, &mut rng);
+
+            // When every row of tiles is the same, the 2D update matches the 1D one.
+            let row = [&previous, &t, &next];
+            let neighbors = [row, row, row];
+            assert_eq!(
+                t.next_generation_2d(&neighbors),
+                t.next_generation(&previous, &next)
+            );
+        }
+    }
+
+    #[test]
+    fn test_quadrants() {
+        let mut rng = StdRng::from_seed([3; 32]);
+        let a: VecTile = Generator::Uniform { density: 0.5 }.generate(8, &mut rng);
+        let b = VecTile::new(8);
+
+        let board = Board2D::new(vec![a.clone(), b.clone()], Layout2D::Quadrants, 3);
+        assert_eq!(board.period(), 6);
+        assert_eq!(board.owner_at(0, 0), 0);
+        assert_eq!(board.owner_at(3, 0), 1);
+        assert_eq!(board.owner_at(3, 3), 0);
+        assert_eq!(board.owner_at(-1, 0), 1);
+        assert_eq!(board.scores(), vec![0, 0]);
+
+        // Nothing can happen when everyone has the same tile.
+        let mut board = Board2D::new(vec![b.clone(); 4], Layout2D::Quadrants, 2);
+        for _ in 0..10 {
+            board = board.next_generation();
+        }
+        assert_eq!(board.scores(), vec![0, 0, 0, 0]);
+
+        let board = Board2D::new(vec![a, b], Layout2D::Diagonal, 3);
+        assert_eq!(board.owner_at(0, 0), 0);
+        assert_eq!(board.owner_at(3, 0), 1);
+        assert_eq!(board.owner_at(4, 1), 1);
+        assert_eq!(board.owner_at(2, 1), 0);
+        assert_eq!(board.owner_at(1, 4), 1);
+    }
+}
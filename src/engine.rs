@@ -0,0 +1,253 @@
+//! Choosing the tile representation to simulate with at runtime.
+
+use board::{Board, VecBoard};
+use score::Scorer;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tile::*;
+
+/// Tile representation to simulate with, chosen at runtime.
+/// Every engine gives identical results (see check_conformance), they only differ in speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Engine {
+    /// VecTile: one bool per cell.
+    VecTile,
+    /// BitTile: one bit per cell.
+    BitTile,
+    /// ByteTile: one byte per cell, with SIMD neighbor counting.
+    ByteTile,
+}
+
+impl Engine {
+    /// Every engine, for example to check they all agree.
+    pub const ALL: [Engine; 3] = [Engine::VecTile, Engine::BitTile, Engine::ByteTile];
+
+    /// The best engine for tiles of this size.
+    /// In benches/evolution.rs (tile_evolution, 8 to 2000 cells) ByteTile is fastest up to 200 cells
+    /// and level with VecTile at 2000, but huge tiles use BitTile, which takes an eighth of the memory.
+    pub fn auto(tile_size: usize) -> Engine {
+        if tile_size > 4096 {
+            Engine::BitTile
+        } else {
+            Engine::ByteTile
+        }
+    }
+
+    /// Short name, as used on the command line and in replays.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Engine::VecTile => "vec",
+            Engine::BitTile => "bit",
+            Engine::ByteTile => "byte",
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    /// Parses the names from Engine::name.
+    fn from_str(s: &str) -> Result<Engine, String> {
+        Engine::ALL
+            .iter()
+            .find(|e| e.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("unknown engine: {} (expected vec, bit or byte)", s))
+    }
+}
+
+/// A VecBoard using whichever tile representation its engine selects.
+#[derive(Debug)]
+pub enum EngineBoard {
+    /// Simulating with Engine::VecTile.
+    VecTile(VecBoard<VecTile>),
+    /// Simulating with Engine::BitTile.
+    BitTile(VecBoard<BitTile>),
+    /// Simulating with Engine::ByteTile.
+    ByteTile(VecBoard<ByteTile>),
+}
+
+// Evaluate $e with $b bound to the board inside any variant of $board.
+macro_rules! dispatch {
+    ($board:expr, $b:pat => $e:expr) => {
+        match $board {
+            EngineBoard::VecTile($b) => $e,
+            EngineBoard::BitTile($b) => $e,
+            EngineBoard::ByteTile($b) => $e,
+        }
+    };
+}
+
+impl EngineBoard {
+    /// Like VecBoard::new_incremental, converting a and b to the engine's tile type,
+    /// with a period matching the backgrounds (see VecBoard::quiescence_period).
+    /// None if a and b are the same.
+    pub fn new(engine: Engine, a: &dyn LifeTileSrc, b: &dyn LifeTileSrc) -> Option<EngineBoard> {
+        match engine {
+            Engine::VecTile => {
+                incremental(VecTile::copy_from(a), VecTile::copy_from(b)).map(EngineBoard::VecTile)
+            }
+            Engine::BitTile => {
+                incremental(BitTile::copy_from(a), BitTile::copy_from(b)).map(EngineBoard::BitTile)
+            }
+            Engine::ByteTile => incremental(ByteTile::copy_from(a), ByteTile::copy_from(b))
+                .map(EngineBoard::ByteTile),
+        }
+    }
+
+    /// The engine this board simulates with.
+    pub fn engine(&self) -> Engine {
+        match *self {
+            EngineBoard::VecTile(_) => Engine::VecTile,
+            EngineBoard::BitTile(_) => Engine::BitTile,
+            EngineBoard::ByteTile(_) => Engine::ByteTile,
+        }
+    }
+
+    /// See Board::step.
+    pub fn step(&mut self) -> bool {
+        dispatch!(*self, ref mut b => b.step())
+    }
+
+    /// See Board::score.
+    pub fn score(&self) -> (isize, isize) {
+        dispatch!(*self, ref b => b.score())
+    }
+
+    /// See Board::score_with.
+    pub fn score_with<S>(&self, scorer: &S) -> (isize, isize)
+    where
+        S: Scorer,
+    {
+        dispatch!(*self, ref b => b.score_with(scorer))
+    }
+
+    /// See Board::lowest_non_a.
+    pub fn lowest_non_a(&self) -> isize {
+        dispatch!(*self, ref b => b.lowest_non_a())
+    }
+
+    /// See Board::highest_non_b.
+    pub fn highest_non_b(&self) -> isize {
+        dispatch!(*self, ref b => b.highest_non_b())
+    }
+
+    /// See Board::contested_width.
+    pub fn contested_width(&self) -> usize {
+        dispatch!(*self, ref b => b.contested_width())
+    }
+
+    /// Is the cell (x, y) of the tile at tile_x alive?
+    pub fn get(&self, tile_x: isize, x: usize, y: usize) -> bool {
+        dispatch!(*self, ref b => b.tile_at(tile_x).get(x, y))
+    }
+
+    /// Hash of the board's snapshot, which is the same whichever engine it uses.
+    pub fn hash(&self, generation: usize) -> u64 {
+        dispatch!(*self, ref b => b.snapshot(generation).hash())
+    }
+
+    /// See Board::print_image.
+    pub fn print_image<Q>(&self, path: Q)
+    where
+        Q: AsRef<Path>,
+    {
+        dispatch!(*self, ref b => b.print_image(path))
+    }
+}
+
+fn incremental<T: LifeTile>(a: T, b: T) -> Option<VecBoard<T>> {
+    let period = VecBoard::quiescence_period(&a, &b, 64);
+    VecBoard::new_incremental(a, b, period)
+}
+
+/// How an engine's evolution differed from VecTile's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The engine which differed.
+    pub engine: Engine,
+    /// First generation they differ at.
+    pub generation: usize,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "engine {} differs from vec at generation {}",
+            self.engine, self.generation
+        )
+    }
+}
+
+/// Run a against b for generations with every engine, checking all boards are identical
+/// to VecTile's after every generation. New tile representations should pass this for many tiles.
+pub fn check_conformance(
+    a: &dyn LifeTileSrc,
+    b: &dyn LifeTileSrc,
+    generations: usize,
+) -> Result<(), Mismatch> {
+    let mut boards: Vec<Option<EngineBoard>> = Engine::ALL
+        .iter()
+        .map(|e| EngineBoard::new(*e, a, b))
+        .collect();
+
+    for generation in 0..=generations {
+        let expected = boards[0].as_ref().map(|x| x.hash(generation));
+        for (engine, x) in Engine::ALL.iter().zip(boards.iter()).skip(1) {
+            if x.as_ref().map(|x| x.hash(generation)) != expected {
+                return Err(Mismatch {
+                    engine: *engine,
+                    generation,
+                });
+            }
+        }
+
+        if generation < generations {
+            for x in boards.iter_mut() {
+                if !x.as_mut().is_some_and(|x| x.step()) {
+                    *x = None;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generate::Generator;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_conformance() {
+        let mut rng = StdRng::from_seed([8; 32]);
+        for size in [3, 5, 8, 17, 40].iter() {
+            for density in [0.1, 0.3, 0.5].iter() {
+                let random = Generator::Uniform { density: *density };
+                let a: VecTile = random.generate(*size, &mut rng);
+                let b = random.generate::<VecTile, _>(*size, &mut rng).mirror();
+                if let Err(m) = check_conformance(&a, &b, 200) {
+                    panic!("size {} density {}: {}", size, density, m);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        for e in Engine::ALL.iter() {
+            assert_eq!(e.name().parse::<Engine>(), Ok(*e));
+        }
+        assert!("simd".parse::<Engine>().is_err());
+    }
+}
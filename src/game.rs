@@ -1,45 +1,157 @@
+//! Playing struggles between tiles, on the various kinds of board.
+
+use arena_board::*;
 use board::*;
+use board_2d::*;
+use engine::*;
+use multi_board::*;
+use score::*;
+use snapshot::*;
+use std::io;
+use std::path::Path;
+use termination::*;
 use tile::*;
 
 // Tile format to use for simulation
 type T = VecTile;
+// Board format to use for simulation
 type B = VecBoard<T>;
 
-// Life Struggle:
-// 1 vs 1 competitive version of Conway's Game of Life
-// Each player (a and b) provide a tile design of the same square dimensions.
-// The world is an infinite plane of tiles,
-// split along x=0, with player a tiles to the -x and player b tiles to the +x
-// Player b's tiles are mirrored so both can be assume enemy tiles to the +x direction.
-// After some fixed number of generations, a score is computed:
-// 1 point added for each tile of enemy territory converted into your tile
-// 1 point deducted for each tile of your territory disrupted.
-// Returns (score_a, score_b).
-pub fn struggle(generations: usize, tile_a: &LifeTileSrc, tile_b: &LifeTileSrc) -> (isize, isize) {
-    let b = struggle_board(generations, tile_a, tile_b);
-
-    match b {
-        Some(x) => {
-            x.print_image("life.png");
-            return x.score();
-        }
-        None => {
-            println!("convergance draw");
-            return (0, 0);
+/// Life Struggle:
+/// 1 vs 1 competitive version of Conway's Game of Life
+/// Each player (a and b) provide a tile design of the same square dimensions.
+/// The world is an infinite plane of tiles,
+/// split along x=0, with player a tiles to the -x and player b tiles to the +x
+/// Player b's tiles are mirrored so both can be assume enemy tiles to the +x direction.
+/// After some fixed number of generations, a score is computed:
+/// 1 point added for each tile of enemy territory converted into your tile
+/// 1 point deducted for each tile of your territory disrupted.
+/// Returns (score_a, score_b), or (0, 0) for a convergence draw.
+pub fn struggle(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+) -> (isize, isize) {
+    struggle_board(generations, tile_a, tile_b).map_or((0, 0), |x| x.score())
+}
+
+/// Like struggle, but returns the board after the given number of generations,
+/// or None in the case of a convergence draw.
+pub fn struggle_board(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+) -> Option<B> {
+    struggle_board_with(generations, tile_a, tile_b, |_| {})
+}
+
+/// Like struggle_board, but calls observe with the board after every generation.
+pub fn struggle_board_with<F>(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+    mut observe: F,
+) -> Option<B>
+where
+    F: FnMut(&B),
+{
+    let bit_tile_a = T::copy_from(tile_a);
+    let bit_tile_b = T::copy_from(tile_b).mirror();
+    let b = B::new(bit_tile_a, bit_tile_b);
+
+    run_board(b, 0, generations, |_, x| observe(x))
+}
+
+/// Like struggle_board, but simulating with the given engine rather than T.
+/// The result is the same whichever engine is used; only the speed differs.
+pub fn struggle_engine(
+    engine: Engine,
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+) -> Option<EngineBoard> {
+    let mirrored_b = VecTile::copy_from(tile_b).mirror();
+    let mut b = EngineBoard::new(engine, tile_a, &mirrored_b)?;
+    for _ in 0..generations {
+        if !b.step() {
+            return None;
         }
     }
+    Some(b)
 }
 
-pub fn struggle_board(generations: usize, tile_a: &LifeTileSrc, tile_b: &LifeTileSrc) -> Option<B> {
+/// Like struggle_board, but saves a snapshot to path every `every` generations,
+/// so a long struggle can be inspected, or resumed with resume_board.
+pub fn struggle_board_checkpointed<Q>(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+    every: usize,
+    path: Q,
+) -> io::Result<Option<B>>
+where
+    Q: AsRef<Path>,
+{
     let bit_tile_a = T::copy_from(tile_a);
     let bit_tile_b = T::copy_from(tile_b).mirror();
-    let mut b = B::new(bit_tile_a, bit_tile_b);
+    let b = B::new(bit_tile_a, bit_tile_b);
 
-    for g in 0..generations {
+    run_board_checkpointed(b, 0, generations, every, path.as_ref())
+}
+
+/// Continue a struggle from a snapshot until it has run for `generations` in total,
+/// still saving a snapshot to path every `every` generations.
+pub fn resume_board<Q>(
+    generations: usize,
+    snapshot: Snapshot<T>,
+    every: usize,
+    path: Q,
+) -> io::Result<Option<B>>
+where
+    Q: AsRef<Path>,
+{
+    let (b, generation) = B::from_snapshot(snapshot);
+    run_board_checkpointed(Some(b), generation, generations, every, path.as_ref())
+}
+
+fn run_board_checkpointed(
+    b: Option<B>,
+    from_generation: usize,
+    generations: usize,
+    every: usize,
+    path: &Path,
+) -> io::Result<Option<B>> {
+    assert!(every > 0);
+    let mut result = Ok(());
+    let b = run_board(b, from_generation, generations, |g, x| {
+        if g % every == 0 && result.is_ok() {
+            result = x.snapshot(g).save(path);
+        }
+    });
+    result.map(|_| b)
+}
+
+/// Run b from generation from_generation up to generations,
+/// calling observe with the generation number and board after every generation.
+fn run_board<F>(
+    mut b: Option<B>,
+    from_generation: usize,
+    generations: usize,
+    mut observe: F,
+) -> Option<B>
+where
+    F: FnMut(usize, &B),
+{
+    for g in from_generation..generations {
         match b {
-            Some(x) => {
+            Some(ref mut x) => {
                 //x.print();
-                b = x.next_generation();
+                if x.step() {
+                    observe(g + 1, x);
+                } else {
+                    b = None;
+                    break;
+                }
             }
             None => {
                 break;
@@ -50,14 +162,164 @@ pub fn struggle_board(generations: usize, tile_a: &LifeTileSrc, tile_b: &LifeTil
         }
     }
 
-    return b;
+    b
 }
 
-#[cfg(test)]
-use test::Bencher;
+/// Like struggle_board, but stops early once the outcome is decided according to policy.
+/// The returned outcome includes the projected score after the full number of generations.
+pub fn struggle_board_until(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+    policy: TerminationPolicy,
+) -> Outcome<B> {
+    let bit_tile_a = T::copy_from(tile_a);
+    let bit_tile_b = T::copy_from(tile_b).mirror();
+    run_until(B::new(bit_tile_a, bit_tile_b), generations, policy)
+}
+
+/// Like struggle_board_until, but simulating with the given engine rather than T.
+/// The outcome is the same whichever engine is used.
+pub fn struggle_engine_until(
+    engine: Engine,
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+    policy: TerminationPolicy,
+) -> Outcome<EngineBoard> {
+    let mirrored_b = VecTile::copy_from(tile_b).mirror();
+    match EngineBoard::new(engine, tile_a, &mirrored_b) {
+        Some(EngineBoard::VecTile(b)) => {
+            run_until(Some(b), generations, policy).map(EngineBoard::VecTile)
+        }
+        Some(EngineBoard::BitTile(b)) => {
+            run_until(Some(b), generations, policy).map(EngineBoard::BitTile)
+        }
+        Some(EngineBoard::ByteTile(b)) => {
+            run_until(Some(b), generations, policy).map(EngineBoard::ByteTile)
+        }
+        None => run_until::<VecTile, VecBoard<VecTile>>(None, generations, policy)
+            .map(EngineBoard::VecTile),
+    }
+}
+
+/// Run b from generation 0 until generations, or until policy decides the outcome.
+fn run_until<U, X>(b: Option<X>, generations: usize, policy: TerminationPolicy) -> Outcome<X>
+where
+    U: LifeTile,
+    X: Board<U>,
+{
+    let mut terminator = Terminator::new(policy, generations);
+    // a and b converged at generation.
+    let draw = |generation: usize, terminator: &Terminator| Outcome {
+        board: None,
+        generation,
+        reason: StopReason::ConvergenceDraw,
+        score: (0, 0),
+        max_width: terminator.max_width(),
+    };
+
+    let mut x = match b {
+        Some(x) => x,
+        None => return draw(0, &terminator),
+    };
+    for g in 0..generations {
+        if let Some((reason, score)) = terminator.check(g, &x) {
+            return Outcome {
+                board: Some(x),
+                generation: g,
+                reason,
+                score,
+                max_width: terminator.max_width(),
+            };
+        }
+        if !x.step() {
+            return draw(g + 1, &terminator);
+        }
+    }
+
+    terminator.observe_width(&x);
+    let score = x.score();
+    Outcome {
+        board: Some(x),
+        generation: generations,
+        reason: StopReason::Completed,
+        score,
+        max_width: terminator.max_width(),
+    }
+}
+
+/// Like struggle, but scores with the given scorer averaged over the last `window` generations.
+pub fn struggle_averaged<S>(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+    scorer: S,
+    window: usize,
+) -> (f64, f64)
+where
+    S: Scorer,
+{
+    let mut averaged = TimeAveragedScorer::new(scorer, window);
+    struggle_board_with(generations, tile_a, tile_b, |x| averaged.observe(x));
+    averaged.average()
+}
+
+/// Free for all Life Struggle between any number of players:
+/// each player's tile fills a band band_width tiles wide, in order, repeating endlessly.
+/// Returns the board after the given number of generations, see MultiBoard::scores.
+pub fn struggle_multi(
+    generations: usize,
+    tiles: &[&dyn LifeTileSrc],
+    band_width: usize,
+) -> MultiBoard<T> {
+    let players = tiles.iter().map(|t| T::copy_from(*t)).collect();
+    let mut b = MultiBoard::new(players, band_width);
+    for _ in 0..generations {
+        b = b.next_generation();
+    }
+    b
+}
+
+/// Life Struggle in 2 dimensions, with territories split according to layout.
+/// Returns the board after the given number of generations, see Board2D::scores.
+pub fn struggle_2d(
+    generations: usize,
+    tiles: &[&dyn LifeTileSrc],
+    layout: Layout2D,
+    region_width: usize,
+) -> Board2D<T> {
+    let players = tiles.iter().map(|t| T::copy_from(*t)).collect();
+    let mut b = Board2D::new(players, layout, region_width);
+    for _ in 0..generations {
+        b = b.next_generation();
+    }
+    b
+}
+
+/// Life Struggle in a finite arena of per_player tiles for each player, bounded by edge.
+/// Returns the board after the given number of generations, see ArenaBoard::score.
+pub fn struggle_arena(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+    per_player: usize,
+    edge: Edge,
+) -> ArenaBoard<T> {
+    let bit_tile_a = T::copy_from(tile_a);
+    let bit_tile_b = T::copy_from(tile_b).mirror();
+    let mut b = ArenaBoard::new(bit_tile_a, bit_tile_b, per_player, edge);
+    for _ in 0..generations {
+        b = b.next_generation();
+    }
+    b
+}
 
+#[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
+    use std::fs;
 
     #[test]
     fn test_lwss_vs_gliders() {
@@ -148,35 +410,181 @@ mod tests {
         }
     }
 
-    #[bench]
-    fn bench_lwss_200(b: &mut Bencher) {
-        let size = 200;
+    #[test]
+    fn test_multi_lwss_vs_empty() {
+        let size = 8;
 
         let mut a = VecTile::new(size);
-        for x in 0..(size / 10) {
-            for y in 0..(size / 10) {
-                lwss_at(&mut a, x * 10, y * 10);
-            }
-        }
+        lwss_at(&mut a, 0, 0);
 
-        b.iter(|| {
-            a = a.next_generation(&a, &a);
-        });
+        let b = VecTile::new(size);
+
+        // Like test_lwss_vs_empty, the lwss band advances 6 tiles into the band to its +x side,
+        // but its band is finite, so it leaves 6 empty tiles behind it.
+        let board = struggle_multi(100, &[&a, &b], 10);
+        assert_eq!(board.period(), 20);
+        assert_eq!(board.scores(), vec![0, 0]);
+
+        // With two empty players, the tiles left behind are credited to the first.
+        let board = struggle_multi(100, &[&b, &a, &b], 10);
+        assert_eq!(board.scores(), vec![6, 0, -6]);
     }
 
-    #[bench]
-    fn bench_lwss_2000(b: &mut Bencher) {
-        let size = 2000;
+    #[test]
+    fn test_arena_lwss_vs_empty() {
+        let size = 8;
 
         let mut a = VecTile::new(size);
-        for x in 0..(size / 10) {
-            for y in 0..(size / 10) {
-                lwss_at(&mut a, x * 10, y * 10);
+        lwss_at(&mut a, 0, 0);
+
+        let b = VecTile::new(size);
+
+        // A ring arena is the same as a 2 player MultiBoard, with b mirrored.
+        let arena = struggle_arena(100, &a, &b, 10, Edge::Ring);
+        let multi = struggle_multi(100, &[&a, &b.mirror()], 10);
+        let (score_a, score_b) = arena.score();
+        assert_eq!(multi.scores(), vec![score_a, score_b]);
+
+        // Without reinforcements, the band of lwss drifts +x,
+        // leaving as many of a's tiles empty as it captures from b.
+        let arena = struggle_arena(96, &a, &b, 10, Edge::Wall);
+        assert_eq!(arena.score(), (0, 0));
+
+        for edge in [Edge::Wall, Edge::Mirror, Edge::Ring].iter() {
+            let arena = struggle_arena(100, &b, &b, 3, *edge);
+            assert_eq!(arena.score(), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_early_termination() {
+        let size = 8;
+
+        let mut block = VecTile::new(size);
+        block.set(3, 3, true);
+        block.set(4, 3, true);
+        block.set(3, 4, true);
+        block.set(4, 4, true);
+
+        let mut blinker = VecTile::new(size);
+        blinker.set(3, 2, true);
+        blinker.set(3, 3, true);
+        blinker.set(3, 4, true);
+
+        let empty = VecTile::new(size);
+
+        let policy = TerminationPolicy::decided(10);
+        let outcome = struggle_board_until(1000, &block, &empty, policy);
+        assert_eq!(outcome.reason, StopReason::Stable);
+        assert!(outcome.generation < 10);
+        assert_eq!(outcome.score, struggle(1000, &block, &empty));
+
+        // Stopping part way through the cycle, so the projected score has to account for the phase.
+        for generations in [999, 1000].iter() {
+            let outcome = struggle_board_until(*generations, &blinker, &block, policy);
+            assert_eq!(outcome.reason, StopReason::Cycle { period: 2 });
+            assert_eq!(outcome.score, struggle(*generations, &blinker, &block));
+            for engine in Engine::ALL.iter() {
+                let with = struggle_engine_until(*engine, *generations, &blinker, &block, policy);
+                assert_eq!(with.reason, outcome.reason);
+                assert_eq!(with.generation, outcome.generation);
+                assert_eq!(with.score, outcome.score);
+            }
+        }
+
+        let mut lwss = VecTile::new(size);
+        lwss_at(&mut lwss, 0, 0);
+        let outcome = struggle_board_until(1000, &lwss, &empty, policy);
+        assert_eq!(outcome.reason, StopReason::Completed);
+        assert_eq!(outcome.generation, 1000);
+        let completed_width = outcome.max_width;
+        assert!(completed_width > 0);
+
+        for on_max_width in [WidthOutcome::Adjudicate, WidthOutcome::ChaoticDraw].iter() {
+            let policy = TerminationPolicy {
+                max_width: Some(completed_width - 1),
+                on_max_width: *on_max_width,
+                ..policy
+            };
+            let outcome = struggle_board_until(1000, &lwss, &empty, policy);
+            assert_eq!(outcome.max_width, completed_width);
+            assert!(outcome.generation < 1000);
+            if *on_max_width == WidthOutcome::ChaoticDraw {
+                assert_eq!(outcome.reason, StopReason::ChaoticDraw);
+                assert_eq!(outcome.score, (0, 0));
+            } else {
+                assert_eq!(outcome.reason, StopReason::Adjudicated);
+                assert_eq!(outcome.score, outcome.board.unwrap().score());
             }
         }
 
-        b.iter(|| {
-            a = a.next_generation(&a, &a);
-        });
+        let policy = TerminationPolicy {
+            capture_threshold: Some(3),
+            ..policy
+        };
+        let outcome = struggle_board_until(1000, &lwss, &empty, policy);
+        assert_eq!(outcome.reason, StopReason::Captured);
+        assert_eq!(outcome.score.0, 4);
+
+        // Convergence draws stop when the players converge: a lone cell dies at once.
+        let mut dot = VecTile::new(size);
+        dot.set(3, 3, true);
+        for engine in Engine::ALL.iter() {
+            let outcome = struggle_engine_until(*engine, 1000, &dot, &empty, policy);
+            assert_eq!(outcome.reason, StopReason::ConvergenceDraw);
+            assert_eq!(outcome.generation, 1);
+            let outcome = struggle_engine_until(*engine, 1000, &empty, &empty, policy);
+            assert_eq!(outcome.reason, StopReason::ConvergenceDraw);
+            assert_eq!(outcome.generation, 0);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_resume() {
+        let size = 8;
+
+        let mut a = VecTile::new(size);
+        lwss_at(&mut a, 0, 0);
+
+        let mut b = VecTile::new(size);
+        b.set(1, 1, true);
+        b.set(2, 1, true);
+        b.set(1, 2, true);
+        b.set(2, 2, true);
+
+        let expected = struggle_board(100, &a, &b).unwrap();
+
+        let board = struggle_board(50, &a, &b).unwrap();
+        let mut bytes = vec![];
+        board.snapshot(50).write(&mut bytes).unwrap();
+        let snapshot = Snapshot::<VecTile>::read(&mut &bytes[..]).unwrap();
+        assert_eq!(snapshot, board.snapshot(50));
+
+        // Corrupt headers are rejected rather than trusted: a huge size, no size,
+        // and more tiles than the file holds.
+        let corrupt = |offset: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + value.len()].copy_from_slice(value);
+            Snapshot::<VecTile>::read(&mut &bytes[..])
+                .unwrap_err()
+                .kind()
+        };
+        assert_eq!(corrupt(7, &[0xff; 4]), io::ErrorKind::InvalidData);
+        assert_eq!(corrupt(7, &[0; 4]), io::ErrorKind::InvalidData);
+        assert_eq!(corrupt(35, &[0xff; 7]), io::ErrorKind::InvalidData);
+
+        let path = env::temp_dir().join("life_struggle_test_snapshot.bin");
+        let resumed = resume_board(100, snapshot, 20, &path).unwrap().unwrap();
+        assert_eq!(resumed.snapshot(100), expected.snapshot(100));
+        assert_eq!(resumed.score(), expected.score());
+
+        // The last checkpoint was at generation 100.
+        let saved = Snapshot::<VecTile>::load(&path).unwrap();
+        assert_eq!(saved, expected.snapshot(100));
+        fs::remove_file(&path).unwrap();
+
+        // Any board can be snapshot, though the layout may differ from VecBoard's.
+        let (from_generic, _) = VecBoard::from_snapshot(Snapshot::from_board(&expected, 100));
+        assert_eq!(from_generic.score(), expected.score());
     }
 }
@@ -0,0 +1,284 @@
+//! Random tiles with controlled density or structure.
+
+use patterns::{Orientation, CATALOG};
+use rand::Rng;
+use random::{below, chance, unit};
+use tile::*;
+
+/// Symmetry of a random tile: one subgroup of the 8 orientations (see patterns::Orientation)
+/// from each family of equivalent ones, the rest being these rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// No symmetry.
+    None,
+    /// Mirrored over x == size/2.
+    Mirror,
+    /// Mirrored over the diagonal x == y.
+    Diagonal,
+    /// Unchanged by a half turn.
+    Rotate2,
+    /// Unchanged by a quarter turn.
+    Rotate4,
+    /// Mirrored over both x == size/2 and y == size/2.
+    Mirrors,
+    /// Mirrored over both diagonals.
+    Diagonals,
+    /// Unchanged by every orientation.
+    Full,
+}
+
+impl Symmetry {
+    /// Every symmetry, from least to most symmetric.
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::None,
+        Symmetry::Mirror,
+        Symmetry::Diagonal,
+        Symmetry::Rotate2,
+        Symmetry::Rotate4,
+        Symmetry::Mirrors,
+        Symmetry::Diagonals,
+        Symmetry::Full,
+    ];
+
+    /// The orientations which leave a tile with this symmetry unchanged.
+    pub fn orientations(&self) -> Vec<Orientation> {
+        let o = |mirror, quarter_turns| Orientation {
+            mirror,
+            quarter_turns,
+        };
+        match *self {
+            Symmetry::None => vec![o(false, 0)],
+            Symmetry::Mirror => vec![o(false, 0), o(true, 0)],
+            Symmetry::Diagonal => vec![o(false, 0), o(true, 3)],
+            Symmetry::Rotate2 => vec![o(false, 0), o(false, 2)],
+            Symmetry::Rotate4 => (0..4).map(|t| o(false, t)).collect(),
+            Symmetry::Mirrors => vec![o(false, 0), o(false, 2), o(true, 0), o(true, 2)],
+            Symmetry::Diagonals => vec![o(false, 0), o(false, 2), o(true, 1), o(true, 3)],
+            Symmetry::Full => Orientation::all(),
+        }
+    }
+}
+
+/// Ways of making random tiles, better starting points for evolution than a coin flip per cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    /// Each cell alive with probability density.
+    Uniform {
+        /// Probability of each cell being alive.
+        density: f64,
+    },
+    /// Like Uniform, but with symmetry.
+    Symmetric {
+        /// Probability of each cell being alive, before applying the symmetry.
+        density: f64,
+        /// Orientations the tile is unchanged by.
+        symmetry: Symmetry,
+    },
+    /// A width x height box of Uniform cells in the middle of the tile, the rest dead.
+    Soup {
+        /// Probability of each cell in the box being alive.
+        density: f64,
+        /// Width of the box, in cells.
+        width: usize,
+        /// Height of the box, in cells.
+        height: usize,
+    },
+    /// count catalog patterns which fit the tile, each at a random place and orientation.
+    Objects {
+        /// Number of patterns to place.
+        count: usize,
+    },
+    /// Smooth random noise, with features around scale cells across, alive where it's below density.
+    /// Wraps around, so repeats without seams like the tile itself if scale divides size.
+    Noise {
+        /// Roughly the fraction of cells alive.
+        density: f64,
+        /// Size of features, in cells.
+        scale: usize,
+    },
+}
+
+impl Generator {
+    /// A generator of a random kind with typical settings for size x size tiles,
+    /// for varied populations.
+    pub fn random<R: Rng>(size: usize, rng: &mut R) -> Generator {
+        let density = 0.2 + 0.3 * unit(rng);
+        match below(rng, 5) {
+            0 => Generator::Uniform { density },
+            1 => Generator::Symmetric {
+                density,
+                symmetry: Symmetry::ALL[below(rng, Symmetry::ALL.len())],
+            },
+            2 => Generator::Soup {
+                density: 0.5,
+                width: (size / 2).max(1),
+                height: (size / 2).max(1),
+            },
+            3 => Generator::Objects {
+                count: 1 + below(rng, 3),
+            },
+            _ => Generator::Noise {
+                density,
+                scale: (size / 4).max(2),
+            },
+        }
+    }
+
+    /// A new random size x size tile.
+    pub fn generate<T, R>(&self, size: usize, rng: &mut R) -> T
+    where
+        T: LifeTile,
+        R: Rng,
+    {
+        let mut t = T::new(size);
+        match *self {
+            Generator::Uniform { density } => {
+                for y in 0..size {
+                    for x in 0..size {
+                        t.set(x, y, chance(rng, density));
+                    }
+                }
+            }
+            Generator::Symmetric { density, symmetry } => {
+                // Draw once for each set of cells the symmetry maps onto each other.
+                let orientations = symmetry.orientations();
+                let mut drawn = vec![false; size * size];
+                for y in 0..size {
+                    for x in 0..size {
+                        if drawn[y * size + x] {
+                            continue;
+                        }
+                        let v = chance(rng, density);
+                        for o in &orientations {
+                            let (ox, oy) = o.apply(x, y, size, size);
+                            drawn[oy * size + ox] = true;
+                            t.set(ox, oy, v);
+                        }
+                    }
+                }
+            }
+            Generator::Soup {
+                density,
+                width,
+                height,
+            } => {
+                let (width, height) = (width.min(size), height.min(size));
+                let (left, top) = ((size - width) / 2, (size - height) / 2);
+                for y in top..top + height {
+                    for x in left..left + width {
+                        t.set(x, y, chance(rng, density));
+                    }
+                }
+            }
+            Generator::Objects { count } => {
+                let fitting: Vec<_> = CATALOG.iter().filter(|p| p.fits(size)).collect();
+                let orientations = Orientation::all();
+                for _ in 0..count {
+                    if fitting.is_empty() {
+                        break;
+                    }
+                    let pattern = fitting[below(rng, fitting.len())];
+                    let orientation = orientations[below(rng, orientations.len())];
+                    let (x, y) = (below(rng, size), below(rng, size));
+                    pattern.stamp(&mut t, x, y, orientation);
+                }
+            }
+            Generator::Noise { density, scale } => {
+                // Value noise: random values on a lattice every scale cells,
+                // interpolated smoothly between.
+                let scale = scale.max(1);
+                let lattice = size.div_ceil(scale);
+                let values: Vec<f64> = (0..lattice * lattice).map(|_| unit(rng)).collect();
+                let at = |lx: usize, ly: usize| values[(ly % lattice) * lattice + lx % lattice];
+                let smooth = |f: f64| f * f * (3.0 - 2.0 * f);
+                for y in 0..size {
+                    for x in 0..size {
+                        let (lx, ly) = (x / scale, y / scale);
+                        let fx = smooth((x % scale) as f64 / scale as f64);
+                        let fy = smooth((y % scale) as f64 / scale as f64);
+                        let top = at(lx, ly) * (1.0 - fx) + at(lx + 1, ly) * fx;
+                        let bottom = at(lx, ly + 1) * (1.0 - fx) + at(lx + 1, ly + 1) * fx;
+                        t.set(x, y, top * (1.0 - fy) + bottom * fy < density);
+                    }
+                }
+            }
+        }
+        t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn density(t: &VecTile) -> f64 {
+        let size = t.size();
+        let live: usize = (0..size)
+            .map(|y| (0..size).filter(|&x| t.get(x, y)).count())
+            .sum();
+        live as f64 / (size * size) as f64
+    }
+
+    #[test]
+    fn test_density() {
+        let mut rng = StdRng::from_seed([4; 32]);
+        for target in [0.1, 0.3, 0.5].iter() {
+            let uniform = Generator::Uniform { density: *target };
+            let t: VecTile = uniform.generate(64, &mut rng);
+            assert!((density(&t) - target).abs() < 0.05);
+            let noise = Generator::Noise {
+                density: *target,
+                scale: 8,
+            };
+            let t: VecTile = noise.generate(64, &mut rng);
+            assert!((density(&t) - target).abs() < 0.25);
+        }
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let mut rng = StdRng::from_seed([5; 32]);
+        for symmetry in Symmetry::ALL.iter() {
+            for size in [7, 8].iter() {
+                let g = Generator::Symmetric {
+                    density: 0.5,
+                    symmetry: *symmetry,
+                };
+                let t: VecTile = g.generate(*size, &mut rng);
+                for o in symmetry.orientations() {
+                    for y in 0..*size {
+                        for x in 0..*size {
+                            let (ox, oy) = o.apply(x, y, *size, *size);
+                            assert_eq!(t.get(x, y), t.get(ox, oy), "{:?} {:?}", symmetry, o);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_soup_and_objects() {
+        let mut rng = StdRng::from_seed([6; 32]);
+        let soup = Generator::Soup {
+            density: 1.0,
+            width: 4,
+            height: 2,
+        };
+        let t: VecTile = soup.generate(8, &mut rng);
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(t.get(x, y), (2..6).contains(&x) && (3..5).contains(&y));
+            }
+        }
+
+        let t: VecTile = Generator::Objects { count: 2 }.generate(8, &mut rng);
+        assert!(density(&t) > 0.0);
+        for _ in 0..20 {
+            let g = Generator::random(8, &mut rng);
+            let _: VecTile = g.generate(8, &mut rng);
+        }
+    }
+}
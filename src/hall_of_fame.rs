@@ -0,0 +1,195 @@
+//! An archive of round champions, played against later rounds to measure progress.
+
+use engine::Engine;
+use game;
+use termination::TerminationPolicy;
+use tile::*;
+
+/// Wins, losses and draws from a series of matches, from one player's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Record {
+    /// Matches with a higher score than the opponent.
+    pub wins: usize,
+    /// Matches with a lower score than the opponent.
+    pub losses: usize,
+    /// Matches with equal scores, including convergence draws.
+    pub draws: usize,
+    /// Total of own score minus opponent's score.
+    pub point_difference: isize,
+}
+
+impl Record {
+    /// Count a match with this score, own score first. None is a convergence draw.
+    pub fn add(&mut self, score: Option<(isize, isize)>) {
+        let (own, other) = score.unwrap_or((0, 0));
+        if own > other {
+            self.wins += 1;
+        } else if other > own {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+        self.point_difference += own - other;
+    }
+
+    /// Number of matches counted.
+    pub fn matches(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+}
+
+/// The best player from one round of evolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Champion<T>
+where
+    T: LifeTile,
+{
+    /// Round of evolution it won.
+    pub round: usize,
+    /// Name of the player.
+    pub name: String,
+    /// The player's tile.
+    pub tile: T,
+}
+
+/// How a champion did against every champion before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Round of evolution the champion won.
+    pub round: usize,
+    /// Name of the champion.
+    pub name: String,
+    /// Record against every champion before it.
+    pub record: Record,
+}
+
+impl Progress {
+    /// Did this champion beat its predecessors more often than it lost to them?
+    pub fn improved(&self) -> bool {
+        self.record.wins > self.record.losses
+    }
+}
+
+/// Archive of the champion of every round of evolution, which are never removed,
+/// so progress can be measured against them: without it, evolution can go round in circles
+/// (rock, paper, scissors) with every round looking like an improvement on the last.
+#[derive(Debug, Clone)]
+pub struct HallOfFame<T>
+where
+    T: LifeTile,
+{
+    champions: Vec<Champion<T>>,
+    generations: usize,
+    engine: Engine,
+    policy: TerminationPolicy,
+}
+
+impl<T> HallOfFame<T>
+where
+    T: LifeTile,
+{
+    /// Matches against champions run for generations, simulated with engine,
+    /// stopping early according to policy (see game::struggle_engine_until).
+    pub fn new(generations: usize, engine: Engine, policy: TerminationPolicy) -> HallOfFame<T> {
+        HallOfFame {
+            champions: vec![],
+            generations,
+            engine,
+            policy,
+        }
+    }
+
+    /// Add the champion of a round.
+    pub fn add(&mut self, round: usize, name: &str, tile: &T) {
+        self.champions.push(Champion {
+            round,
+            name: name.to_string(),
+            tile: tile.clone(),
+        });
+    }
+
+    /// Oldest first.
+    pub fn champions(&self) -> &[Champion<T>] {
+        &self.champions
+    }
+
+    /// tile's record playing against every champion.
+    pub fn challenge(&self, tile: &T) -> Record {
+        self.record_against(tile, &self.champions)
+    }
+
+    /// Each champion's record against all the champions before it.
+    /// The first champion has no predecessors, so is left out.
+    pub fn progress(&self) -> Vec<Progress> {
+        (1..self.champions.len())
+            .map(|i| {
+                let c = &self.champions[i];
+                Progress {
+                    round: c.round,
+                    name: c.name.clone(),
+                    record: self.record_against(&c.tile, &self.champions[..i]),
+                }
+            }).collect()
+    }
+
+    fn record_against(&self, tile: &T, champions: &[Champion<T>]) -> Record {
+        let mut record = Record::default();
+        for c in champions {
+            let outcome = game::struggle_engine_until(
+                self.engine,
+                self.generations,
+                tile,
+                &c.tile,
+                self.policy,
+            );
+            record.add(outcome.board.as_ref().map(|_| outcome.score));
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use patterns;
+
+    #[test]
+    fn test_progress() {
+        let mut hall = HallOfFame::new(200, Engine::ByteTile, TerminationPolicy::decided(16));
+        let roster = patterns::classic_roster::<VecTile>(8);
+        for (round, name) in ["Empty", "Glider", "Lwss"].iter().enumerate() {
+            let (_, tile) = roster.iter().find(|(n, _)| n == name).unwrap();
+            hall.add(round, name, tile);
+        }
+
+        let progress = hall.progress();
+        assert_eq!(progress.len(), 2);
+        for (i, p) in progress.iter().enumerate() {
+            assert_eq!(p.round, i + 1);
+            assert_eq!(p.record.matches(), i + 1);
+        }
+
+        // Playing a champion against itself is a convergence draw.
+        let glider = &hall.champions()[1].tile;
+        let record = hall.challenge(glider);
+        assert_eq!(record.matches(), 3);
+        assert!(record.draws >= 1);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut record = Record::default();
+        record.add(Some((3, 1)));
+        record.add(Some((0, 2)));
+        record.add(None);
+        assert_eq!(
+            record,
+            Record {
+                wins: 1,
+                losses: 1,
+                draws: 1,
+                point_difference: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Life Struggle: 1 vs 1 competitive version of Conway's Game of Life.
+//!
+//! Each player provides a tile design of the same square dimensions.
+//! The world is an infinite plane of tiles, split along x=0,
+//! with each side covered with one of the players tiles, tiled endlessly.
+//! After some fixed number of generations, the board is scored (see `score`).
+//!
+//! The main entry points are:
+//! - `LifeTile` implementations (`VecTile`, `BitTile`, `ByteTile`) for player designs,
+//! - `Engine` / `EngineBoard` to choose which of those to simulate with at runtime,
+//! - `game::struggle` and `game::struggle_board` to play a match,
+//! - `Board` / `VecBoard` to inspect and score the result.
+#![warn(missing_docs)]
+extern crate fixedbitset;
+extern crate image;
+extern crate num_integer;
+extern crate rand;
+extern crate rayon;
+
+pub mod arena_board;
+pub mod board;
+pub mod board_2d;
+pub mod engine;
+pub mod game;
+pub mod generate;
+pub mod hall_of_fame;
+pub mod multi_board;
+pub mod novelty;
+pub mod optimize;
+pub mod patterns;
+pub mod player;
+mod random;
+pub mod reference;
+pub mod replay;
+pub mod report;
+pub mod score;
+pub mod snapshot;
+pub mod stability;
+pub mod termination;
+pub mod tile;
+pub mod tournament;
+
+pub use board::{Board, VecBoard};
+pub use engine::{Engine, EngineBoard};
+pub use tile::{BitTile, ByteTile, LifeTile, LifeTileSrc, VecTile};
@@ -1,148 +1,324 @@
-#![feature(test)]
-mod board;
-mod game;
-mod tile;
-extern crate image;
+extern crate life_struggle;
 extern crate rand;
-extern crate rayon;
-extern crate test;
 extern crate time;
-use board::Board;
+use life_struggle::generate::Generator;
+use life_struggle::hall_of_fame::HallOfFame;
+use life_struggle::novelty::{self, MapElites, NoveltyArchive};
+use life_struggle::optimize;
+use life_struggle::patterns;
+use life_struggle::player::Player;
+use life_struggle::reference;
+use life_struggle::replay::Replay;
+use life_struggle::report::Report;
+use life_struggle::stability::Filter;
+use life_struggle::termination::{TerminationPolicy, WidthOutcome};
+use life_struggle::tile::*;
+use life_struggle::tournament::{self, RoundRobin, Tournament};
+use life_struggle::Engine;
 use rand::Rng;
-use std::cmp::Ordering;
-use tile::*;
+use std::env;
+use std::fs;
+use std::process;
 
 type T = VecTile;
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 1 && args[1] == "replay" {
+        process::exit(replay_files(&args[2..]));
+    }
+    if args.len() > 1 && args[1] == "optimize" {
+        let iterations = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(500);
+        optimize_random(iterations);
+        return;
+    }
+    if args.len() > 1 && args[1] == "elites" {
+        let iterations = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(500);
+        elites_random(iterations);
+        return;
+    }
+    if args.len() > 1 && args[1] == "verify" {
+        let matches = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(100);
+        process::exit(verify_random(matches));
+    }
+
+    // Simulate with --engine <vec|bit|byte>, or pick the best for the tile size.
+    let engine = match args.iter().position(|a| a == "--engine") {
+        Some(i) => match args.get(i + 1).map(|name| name.parse::<Engine>()) {
+            Some(Ok(engine)) => Some(engine),
+            Some(Err(e)) => {
+                println!("{}", e);
+                process::exit(2);
+            }
+            None => {
+                println!("--engine needs a name: vec, bit or byte");
+                process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    // Rank players with --format <round-robin|groups:n|swiss:rounds|knockout|double-elimination>.
+    let tournament = match args.iter().position(|a| a == "--format") {
+        Some(i) => match args.get(i + 1).map(|name| tournament::parse(name)) {
+            Some(Ok(tournament)) => tournament,
+            Some(Err(e)) => {
+                println!("{}", e);
+                process::exit(2);
+            }
+            None => {
+                println!("--format needs a tournament format, such as round-robin or swiss:4");
+                process::exit(2);
+            }
+        },
+        None => Box::new(RoundRobin),
+    };
+
     println!("Life Struggle");
-    struggle_random();
+    struggle_random(engine, &*tournament);
 }
 
-#[derive(Debug, Clone)]
-struct Player {
-    tile: T,
-    name: String,
-    wins: usize,
-    losses: usize,
-    point_difference: isize,
-    keep: bool,
+// Rerun recorded matches, reporting any which don't end as recorded.
+// Returns the process exit code.
+fn replay_files(paths: &[String]) -> i32 {
+    let mut code = 0;
+    for path in paths {
+        match Replay::<T>::load(path) {
+            Ok(r) => match r.verify() {
+                Ok(()) => println!("{}: ok ({:?})", path, r.score),
+                Err(d) => {
+                    println!("{}: DIVERGED, simulator regression? {}", path, d);
+                    code = 1;
+                }
+            },
+            Err(e) => {
+                println!("{}: failed to load: {}", path, e);
+                code = 2;
+            }
+        }
+    }
+    code
 }
 
-impl Player {
-    fn new(tile: T, name: String, keep: bool) -> Player {
-        Player {
-            tile: tile,
-            name: name,
-            wins: 0,
-            losses: 0,
-            point_difference: 0,
-            keep: keep,
+// Play random matches against the reference simulator, reporting any which diverge.
+// Returns the process exit code.
+fn verify_random(matches: usize) -> i32 {
+    let size = 8;
+    let generations = 300;
+    let mut rng = rand::thread_rng();
+    let mut code = 0;
+    for i in 0..matches {
+        let random = Generator::Uniform { density: 0.5 };
+        let tiles: [T; 2] = [
+            random.generate(size, &mut rng),
+            random.generate(size, &mut rng),
+        ];
+        if let Err(d) = reference::verify_board::<T>(generations, &tiles[0], &tiles[1]) {
+            println!("match {}: {}", i, d);
+            println!("a:");
+            tiles[0].print();
+            println!("b:");
+            tiles[1].print();
+            code = 1;
         }
     }
+    println!("verified {} matches of {} generations", matches, generations);
+    code
 }
 
-pub fn struggle_random() {
+// Anneal a random tile against the classic roster, printing progress and the best tile found.
+fn optimize_random(iterations: usize) {
     let size = 8;
     let mut rng = rand::thread_rng();
+    let opponents: Vec<T> = patterns::classic_roster::<T>(size)
+        .into_iter()
+        .map(|(_, t)| t)
+        .collect();
+    let start: T = Generator::Uniform { density: 0.5 }.generate(size, &mut rng);
 
-    let player_empty = Player::new(T::new(size), "Empty".to_string(), true);
-    let mut player_glider = Player::new(T::new(size), "Glider".to_string(), true);
-    {
-        let mut q = |x: usize, y: usize| player_glider.tile.set(x, y, true);
-        q(0, 2);
-        q(1, 2);
-        q(2, 2);
-        q(2, 1);
-        q(1, 0);
+    let config = optimize::Config {
+        iterations,
+        ..Default::default()
+    };
+    let result = optimize::optimize(&start, &opponents, &config, &mut rng);
+    println!("start: {}", result.start_fitness);
+    for (i, step) in result.trajectory.iter().enumerate() {
+        if step.accepted || i % 50 == 0 {
+            println!(
+                "{}: {} (best {}){}",
+                i,
+                step.fitness,
+                step.best_fitness,
+                if step.accepted { " accepted" } else { "" }
+            );
+        }
     }
+    println!(
+        "best: {} after evaluating {} tiles against {} opponents",
+        result.best_fitness,
+        result.evaluations,
+        opponents.len()
+    );
+    result.best.print();
+}
 
-    let mut player_lwss = Player::new(T::new(size), "Lwss".to_string(), true);
-    {
-        let mut q = |x: usize, y: usize| player_lwss.tile.set(x, y, true);
-
-        // Light weight space ship going +x
-        q(0, 0);
-        q(0, 2);
-        q(1, 3);
-        q(2, 3);
-        q(3, 3);
-        q(4, 3);
-        q(4, 2);
-        q(4, 2);
-        q(4, 1);
-        q(3, 0);
-    }
+// MAP-Elites against the classic roster: keep the best tile for each kind of behavior,
+// mutating elites with novel behaviors, then print the elites found.
+fn elites_random(iterations: usize) {
+    let size = 8;
+    let generations = 1000;
+    let mut rng = rand::thread_rng();
+    let opponents: Vec<T> = patterns::classic_roster::<T>(size)
+        .into_iter()
+        .map(|(_, t)| t)
+        .collect();
+    let filter = Filter::default();
+    let mut elites = MapElites::default();
+    let mut archive = NoveltyArchive::new(5, 1.0);
 
-    let mut players = Vec::<Player>::with_capacity(10);
+    for i in 0..(iterations + 20) {
+        // Start from random tiles, then mutate elites, favoring novel ones.
+        let (name, tile) = match elites.choose_novel(&archive, &mut rng) {
+            Some(e) if i >= 20 => (
+                format!("Elite {}", i),
+                optimize::mutate(&e.player.tile, 3, &mut rng),
+            ),
+            _ => (
+                format!("Random {}", i),
+                random_tile(size, &filter, &mut rng),
+            ),
+        };
+        let evaluation = novelty::evaluate(&tile, &opponents, generations);
+        let novelty = archive.consider(&evaluation.behavior);
+        if elites.insert(name.clone(), tile, &evaluation) {
+            println!(
+                "{}: {} (novelty {:.2}, {} cells filled)",
+                name,
+                evaluation.record.point_difference,
+                novelty,
+                elites.len()
+            );
+        }
+    }
 
-    players.push(player_empty);
-    players.push(player_glider);
-    players.push(player_lwss);
+    println!(
+        "{} cells filled, {} novel behaviors archived",
+        elites.len(),
+        archive.len()
+    );
+    for e in elites.elites() {
+        let p = &e.player;
+        println!(
+            "{}: {}  {}  {}  {:?}",
+            p.name, p.wins, p.losses, p.point_difference, e.behavior
+        );
+        p.tile.print();
+    }
+}
 
-    for i in 0..7 {
-        let mut t = T::new(size);
-        for y in 0..size {
-            for x in 0..size {
-                t.set(x, y, rng.gen());
-            }
+// A random tile from a random generator, accepted by filter,
+// or the last one tried if it rejects too many.
+fn random_tile<R: Rng>(size: usize, filter: &Filter, rng: &mut R) -> T {
+    let mut t = T::new(size);
+    for _ in 0..100 {
+        t = Generator::random(size, rng).generate(size, rng);
+        if filter.accept_tile(&t, rng) {
+            break;
         }
+    }
+    t
+}
 
+pub fn struggle_random(engine: Option<Engine>, tournament: &dyn Tournament) {
+    let size = 8;
+    let engine = engine.unwrap_or_else(|| Engine::auto(size));
+    println!("Engine: {}", engine);
+    println!("Tournament: {}", tournament.name());
+    let mut rng = rand::thread_rng();
+
+    // Baseline players from the pattern catalog, kept so evolved tiles always face them.
+    let mut players: Vec<Player<T>> = patterns::classic_roster::<T>(size)
+        .into_iter()
+        .map(|(name, tile)| Player::new(tile, name, true))
+        .collect();
+    // Each round, the best 3 other players survive.
+    let max_survivors = players.len() + 3;
+
+    // Skip random tiles which die out or settle down on their own.
+    let filter = Filter::default();
+    for i in 0..7 {
+        let t = random_tile(size, &filter, &mut rng);
         players.push(Player::new(t, format!("Random {}", i), false));
     }
 
     let c_players = players.len();
     let generations = 1000;
+    // Matches stop once stable or cycling, with the score they would have finished with,
+    // or are scored as they stand once wider than a c/2 spaceship gets in a match,
+    // so a tile spraying endless debris can't make a round arbitrarily slow.
+    let policy = TerminationPolicy {
+        max_width: Some(generations / 2 / size + 2),
+        on_max_width: WidthOutcome::Adjudicate,
+        ..TerminationPolicy::decided(64)
+    };
+    let mut hall = HallOfFame::new(generations, engine, policy);
+    fs::create_dir_all("./replays").unwrap();
     for evolve_gen in 0..10 {
-        for i in 0..(c_players - 1) {
-            for i2 in (i + 1)..c_players {
-                let (part_1, part_2) = players.split_at_mut(i2);
-                let pa = &mut part_1[i];
-                let pb = &mut part_2[0];
-                let board = {
-                    let a = &pa.tile;
-                    let b = &pb.tile;
-                    let b_mirror = b.mirror_over_x();
-                    let b = if rng.gen() { &b_mirror } else { b };
-                    game::struggle_board(generations, a, b)
-                };
+        // Browsable results in ./reports/round-<n>, with images of every decided match.
+        let standings = {
+            let players = &players;
+            let mut report = Report::new(
+                format!("./reports/round-{:02}", evolve_gen),
+                &format!("Round {} ({})", evolve_gen, tournament.name()),
+                players,
+            ).unwrap();
+            let standings = {
+                // Numbered, as formats such as Swiss can pair the same players more than once.
+                let mut played = 0;
+                let mut play = |i: usize, i2: usize| {
+                    let pa = &players[i];
+                    let pb = &players[i2];
+                    let (replay, outcome) = Replay::record_until(
+                        engine,
+                        generations,
+                        &pa.tile,
+                        &pb.tile,
+                        rng.gen(),
+                        0,
+                        policy,
+                    );
+                    replay
+                        .save(format!(
+                            "./replays/{:02}-{:03}-{}-{}.replay",
+                            evolve_gen, played, pa.name, pb.name
+                        )).unwrap();
+                    played += 1;
 
-                match board {
-                    Some(x) => {
-                        let (a_s, b_s) = x.score();
-                        let ref mut fout = format!(
-                            "./images/{:03}-{:02}: {} to {}.png",
-                            pa.name, pb.name, a_s, b_s
-                        );
-                        x.print_image(fout);
-                        if a_s > b_s {
-                            pa.wins += 1;
-                            pb.losses += 1;
-                        } else if b_s > a_s {
-                            pb.wins += 1;
-                            pa.losses += 1;
-                        }
-                        pa.point_difference += a_s - b_s;
-                        pb.point_difference -= a_s - b_s;
-                    }
-                    None => {
-                        println!("convergance draw: {:03}-{:02}", pa.name, pb.name);
+                    match outcome.board {
+                        Some(ref x) => x.print_image(report.match_image_path(i, i2)),
+                        None => println!("convergance draw: {:03}-{:02}", pa.name, pb.name),
                     }
-                }
-            }
-        }
+                    report.add_match(i, i2, &outcome, outcome.board.is_some());
+                    outcome.board.as_ref().map(|_| outcome.score)
+                };
+                tournament.run(players.len(), &mut play)
+            };
+            report.write(&standings).unwrap();
+            standings
+        };
         println!("Results:");
 
-        players.sort_by(|a, b| {
-            let ord = b.wins.cmp(&a.wins);
-            if ord == Ordering::Equal {
-                return b.point_difference.cmp(&a.point_difference);
-            } else {
-                return ord;
-            }
-        });
+        // Best first, so the next round is seeded by this one.
+        let mut unranked: Vec<Option<Player<T>>> = players.drain(..).map(Some).collect();
+        for s in &standings {
+            let mut p = unranked[s.player].take().unwrap();
+            p.wins = s.record.wins;
+            p.losses = s.record.losses;
+            p.point_difference = s.record.point_difference;
+            players.push(p);
+        }
 
-        for ref p in &players {
+        for p in &players {
             println!(
                 "{}: {}  {}  {}",
                 p.name, p.wins, p.losses, p.point_difference
@@ -150,12 +326,24 @@ pub fn struggle_random() {
             p.tile.print();
         }
 
+        hall.add(evolve_gen, &players[0].name, &players[0].tile);
+        if evolve_gen % 3 == 2 {
+            println!("Against the hall of fame:");
+            for p in &players {
+                let r = hall.challenge(&p.tile);
+                println!(
+                    "{}: {}  {}  {}  {}",
+                    p.name, r.wins, r.losses, r.draws, r.point_difference
+                );
+            }
+        }
+
         for i in 0..c_players {
-            if players.len() <= 6 {
+            if players.len() <= max_survivors {
                 break;
             }
             let index = c_players - 1 - i;
-            if players[index].keep == false {
+            if !players[index].keep {
                 println!("Removing Player {}", &players[index].name);
                 players.swap_remove(index);
             }
@@ -163,13 +351,7 @@ pub fn struggle_random() {
 
         let mut i = 0;
         while players.len() < c_players {
-            let mut t = T::new(size);
-            for y in 0..size {
-                for x in 0..size {
-                    t.set(x, y, rng.gen());
-                }
-            }
-
+            let t = random_tile(size, &filter, &mut rng);
             players.push(Player::new(
                 t,
                 format!("Random({}:{})", evolve_gen, i),
@@ -184,4 +366,19 @@ pub fn struggle_random() {
             p.point_difference = 0;
         }
     }
+
+    // Did each round's champion actually beat the ones before it?
+    println!("Hall of fame progress:");
+    for p in hall.progress() {
+        println!(
+            "round {} {}: {}  {}  {}  {}{}",
+            p.round,
+            p.name,
+            p.record.wins,
+            p.record.losses,
+            p.record.draws,
+            p.record.point_difference,
+            if p.improved() { "" } else { "  (no progress)" }
+        );
+    }
 }
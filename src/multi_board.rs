@@ -0,0 +1,146 @@
+//! Free for all boards with more than two players.
+
+use board::save_image;
+use rayon::prelude::*;
+use std::path::Path;
+use tile::*;
+
+/// Life Struggle for more than two players.
+/// The line of tiles is split into bands of tiles, each owned by one player and filled
+/// with that player's tile, bounded by the neighbouring bands on each side.
+/// The sequence of bands repeats endlessly (ex: A | B | C | A | B | C ...),
+/// so only one period of it is simulated, wrapping around at the ends.
+///
+/// Unlike the 2 player Board, tiles are not mirrored: every band has enemies on both sides.
+#[derive(Debug, Clone)]
+pub struct MultiBoard<T>
+where
+    T: LifeTile,
+{
+    // Each player's tile, evolved alone.
+    players: Vec<T>,
+    // Which player owns each tile position in the period.
+    owners: Vec<usize>,
+    tiles: Vec<T>,
+}
+
+impl<T> MultiBoard<T>
+where
+    T: LifeTile,
+{
+    /// Bands of band_width tiles for each player in order.
+    pub fn new(players: Vec<T>, band_width: usize) -> MultiBoard<T> {
+        let bands: Vec<(usize, usize)> = (0..players.len()).map(|p| (p, band_width)).collect();
+        MultiBoard::with_bands(players, &bands)
+    }
+
+    /// Bands given as (player, width) pairs, in order along x.
+    /// A player can have more than one band, for example A | B | A | C.
+    pub fn with_bands(players: Vec<T>, bands: &[(usize, usize)]) -> MultiBoard<T> {
+        assert!(!players.is_empty());
+        let size = players[0].size();
+        assert!(players.iter().all(|p| p.size() == size));
+
+        let mut owners = vec![];
+        for &(player, width) in bands {
+            assert!(player < players.len());
+            for _ in 0..width {
+                owners.push(player);
+            }
+        }
+        assert!(!owners.is_empty());
+
+        let tiles = owners.iter().map(|&o| players[o].clone()).collect();
+        MultiBoard {
+            players,
+            owners,
+            tiles,
+        }
+    }
+
+    /// The board one generation later.
+    pub fn next_generation(&self) -> MultiBoard<T> {
+        let players = self
+            .players
+            .par_iter()
+            .map(|p| p.next_generation(p, p))
+            .collect();
+
+        let tiles = (0..self.tiles.len() as isize)
+            .into_par_iter()
+            .map(|x| {
+                self.tile_at(x)
+                    .next_generation(self.tile_at(x - 1), self.tile_at(x + 1))
+            }).collect();
+
+        MultiBoard {
+            players,
+            owners: self.owners.clone(),
+            tiles,
+        }
+    }
+
+    /// See capture_scores.
+    pub fn scores(&self) -> Vec<isize> {
+        capture_scores(&self.players, &self.owners, &self.tiles)
+    }
+
+    /// Number of players.
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// The current tile of a player, evolved alone.
+    pub fn player_current(&self, player: usize) -> &T {
+        &self.players[player]
+    }
+
+    /// Number of tiles before the bands repeat.
+    pub fn period(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Index of the player whose territory the tile at x is in.
+    pub fn owner_at(&self, x: isize) -> usize {
+        self.owners[self.wrap(x)]
+    }
+
+    /// The tile at x, which can be anywhere on the infinite line of tiles.
+    pub fn tile_at(&self, x: isize) -> &T {
+        &self.tiles[self.wrap(x)]
+    }
+
+    /// Save one period of the board as an image (see board::save_image).
+    pub fn print_image<Q>(&self, path: Q)
+    where
+        Q: AsRef<Path>,
+    {
+        let tiles: Vec<&T> = self.tiles.iter().collect();
+        save_image(&tiles, path);
+    }
+
+    fn wrap(&self, x: isize) -> usize {
+        x.rem_euclid(self.tiles.len() as isize) as usize
+    }
+}
+
+/// Generalization of the 2 player capture score, one score per player:
+/// 1 point added for each tile of enemy territory converted into your tile,
+/// 1 point deducted for each tile of your territory disrupted.
+/// If several players currently have the same tile, converted tiles count for the first of them.
+pub fn capture_scores<T>(players: &[T], owners: &[usize], tiles: &[T]) -> Vec<isize>
+where
+    T: LifeTile,
+{
+    let mut scores = vec![0; players.len()];
+    for (t, &owner) in tiles.iter().zip(owners.iter()) {
+        if t == &players[owner] {
+            continue;
+        }
+        scores[owner] -= 1;
+        if let Some(p) = players.iter().position(|p| p == t) {
+            scores[p] += 1;
+        }
+    }
+    scores
+}
@@ -0,0 +1,398 @@
+//! Behavior descriptors for tiles, novelty search and MAP-Elites.
+
+use board::Board;
+use game;
+use hall_of_fame::Record;
+use player::Player;
+use rand::Rng;
+use random::below;
+use std::collections::HashMap;
+use tile::*;
+
+/// What a tile does in its matches, as opposed to how well it scores:
+/// used to tell strategies apart, so search can keep many different ones.
+/// Averaged over all the tile's matches, from its own point of view (as player a).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Behavior {
+    /// Cells per generation the middle of the contested region moved into the opponent's side.
+    pub frontier_speed: f64,
+    /// Final width of the contested region, in tiles.
+    pub contested_width: f64,
+    /// Fraction of live cells in the final contested region.
+    pub density: f64,
+    /// Separate objects (8 connected groups of live cells) in the final contested region.
+    pub objects: f64,
+    /// The tile's period evolved alone (see LifeTile::self_period), if at most 64.
+    pub self_period: Option<usize>,
+}
+
+impl Behavior {
+    /// As a point in space, with each dimension scaled so typical differences are around 1.
+    pub fn to_vector(&self) -> [f64; 5] {
+        [
+            self.frontier_speed * 4.0,
+            self.contested_width / 4.0,
+            self.density * 4.0,
+            self.objects / 4.0,
+            self.self_period.map_or(0.0, |p| 1.0 + p as f64 / 4.0),
+        ]
+    }
+
+    /// Euclidean distance between the two behaviors' vectors (see to_vector).
+    pub fn distance(&self, other: &Behavior) -> f64 {
+        let a = self.to_vector();
+        let b = other.to_vector();
+        a.iter()
+            .zip(b.iter())
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Coarse bins for each dimension, identifying a MapElites cell.
+    pub fn cell(&self) -> [usize; 5] {
+        let clamp = |v: f64, bins: usize| (v.max(0.0) as usize).min(bins - 1);
+        let log2 = |v: f64| (v + 1.0).log2();
+        [
+            clamp((self.frontier_speed + 0.5) * 5.0, 5),
+            clamp(log2(self.contested_width), 5),
+            clamp(self.density * 8.0, 4),
+            clamp(log2(self.objects), 5),
+            self.self_period.map_or(0, |p| p.min(3)),
+        ]
+    }
+}
+
+/// A tile's results and behavior against a set of opponents.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Results of the matches.
+    pub record: Record,
+    /// Behavior averaged over the matches.
+    pub behavior: Behavior,
+}
+
+/// Play tile against each opponent for generations (see game::struggle_board).
+pub fn evaluate<T>(tile: &T, opponents: &[T], generations: usize) -> Evaluation
+where
+    T: LifeTile,
+{
+    let mut record = Record::default();
+    let mut sums = [0.0; 4];
+    for opponent in opponents {
+        let board = game::struggle_board(generations, tile, opponent);
+        record.add(board.as_ref().map(|b| b.score()));
+        if let Some(b) = board {
+            let frontier = (b.lowest_non_a() + b.highest_non_b() + 1) as f64 / 2.0;
+            sums[0] += frontier * b.tile_size() as f64 / generations.max(1) as f64;
+            sums[1] += b.contested_width() as f64;
+            let (live, cells, objects) = contested_cells(&b);
+            sums[2] += if cells > 0 {
+                live as f64 / cells as f64
+            } else {
+                0.0
+            };
+            sums[3] += objects as f64;
+        }
+    }
+    let n = opponents.len().max(1) as f64;
+    Evaluation {
+        record,
+        behavior: Behavior {
+            frontier_speed: sums[0] / n,
+            contested_width: sums[1] / n,
+            density: sums[2] / n,
+            objects: sums[3] / n,
+            self_period: tile.self_period(64),
+        },
+    }
+}
+
+// Live cells, total cells and objects in board's contested region.
+// Objects are counted in the region alone, wrapping along y as the board does.
+fn contested_cells<T, B>(board: &B) -> (usize, usize, usize)
+where
+    T: LifeTile,
+    B: Board<T>,
+{
+    let size = board.tile_size();
+    let width = board.contested_width() * size;
+    let first = board.lowest_non_a();
+    let live = |x: usize, y: usize| board.tile_at(first + (x / size) as isize).get(x % size, y);
+
+    let mut count = 0;
+    let mut objects = 0;
+    let mut seen = vec![false; width * size];
+    for y in 0..size {
+        for x in 0..width {
+            if !live(x, y) {
+                continue;
+            }
+            count += 1;
+            if seen[y * width + x] {
+                continue;
+            }
+            // Flood fill a new object.
+            objects += 1;
+            seen[y * width + x] = true;
+            let mut stack = vec![(x, y)];
+            while let Some((cx, cy)) = stack.pop() {
+                for dy in [size - 1, 0, 1].iter() {
+                    for dx in -1isize..=1 {
+                        let nx = cx as isize + dx;
+                        let ny = (cy + dy) % size;
+                        if nx < 0 || nx >= width as isize {
+                            continue;
+                        }
+                        let nx = nx as usize;
+                        if !seen[ny * width + nx] && live(nx, ny) {
+                            seen[ny * width + nx] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    (count, width * size, objects)
+}
+
+/// Archive of behaviors seen so far, for novelty search: rewarding tiles for behaving
+/// differently from everything before them, rather than for winning.
+#[derive(Debug, Clone)]
+pub struct NoveltyArchive {
+    behaviors: Vec<Behavior>,
+    /// Novelty is the mean distance to this many nearest archived behaviors.
+    k: usize,
+    /// Behaviors at least this novel are added to the archive.
+    threshold: f64,
+}
+
+impl NoveltyArchive {
+    /// An empty archive. See the fields for k and threshold.
+    pub fn new(k: usize, threshold: f64) -> NoveltyArchive {
+        NoveltyArchive {
+            behaviors: vec![],
+            k,
+            threshold,
+        }
+    }
+
+    /// Mean distance from behavior to its k nearest archived behaviors.
+    /// Infinite while the archive is empty.
+    pub fn novelty(&self, behavior: &Behavior) -> f64 {
+        let mut distances: Vec<f64> = self
+            .behaviors
+            .iter()
+            .map(|b| b.distance(behavior))
+            .collect();
+        if distances.is_empty() {
+            return f64::INFINITY;
+        }
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let nearest = &distances[..self.k.min(distances.len())];
+        nearest.iter().sum::<f64>() / nearest.len() as f64
+    }
+
+    /// Add behavior if it is novel enough. Returns its novelty.
+    pub fn consider(&mut self, behavior: &Behavior) -> f64 {
+        let novelty = self.novelty(behavior);
+        if novelty >= self.threshold {
+            self.behaviors.push(*behavior);
+        }
+        novelty
+    }
+
+    /// Number of archived behaviors.
+    pub fn len(&self) -> usize {
+        self.behaviors.len()
+    }
+
+    /// Are there no archived behaviors?
+    pub fn is_empty(&self) -> bool {
+        self.behaviors.is_empty()
+    }
+}
+
+/// The best player found so far in one cell of a MapElites archive.
+#[derive(Debug, Clone)]
+pub struct Elite<T>
+where
+    T: LifeTile,
+{
+    /// The tile, named, with its results.
+    pub player: Player<T>,
+    /// Its behavior, which decides its cell.
+    pub behavior: Behavior,
+}
+
+/// MAP-Elites archive: the best player (by point difference) for each cell of behavior space
+/// (see Behavior::cell), so search keeps diverse strategies which are each good at what they do.
+#[derive(Debug, Clone)]
+pub struct MapElites<T>
+where
+    T: LifeTile,
+{
+    elites: HashMap<[usize; 5], Elite<T>>,
+}
+
+impl<T> Default for MapElites<T>
+where
+    T: LifeTile,
+{
+    fn default() -> Self {
+        MapElites {
+            elites: HashMap::new(),
+        }
+    }
+}
+
+impl<T> MapElites<T>
+where
+    T: LifeTile,
+{
+    /// Add tile, evaluated as evaluation, if its cell is empty or it beats the cell's elite.
+    /// Returns whether it was added.
+    pub fn insert(&mut self, name: String, tile: T, evaluation: &Evaluation) -> bool {
+        let cell = evaluation.behavior.cell();
+        let r = &evaluation.record;
+        if let Some(e) = self.elites.get(&cell) {
+            if e.player.point_difference >= r.point_difference {
+                return false;
+            }
+        }
+        let mut player = Player::new(tile, name, false);
+        player.wins = r.wins;
+        player.losses = r.losses;
+        player.point_difference = r.point_difference;
+        self.elites.insert(
+            cell,
+            Elite {
+                player,
+                behavior: evaluation.behavior,
+            },
+        );
+        true
+    }
+
+    /// Best first.
+    pub fn elites(&self) -> Vec<&Elite<T>> {
+        let mut elites: Vec<&Elite<T>> = self.elites.values().collect();
+        elites.sort_by(|a, b| {
+            b.player
+                .point_difference
+                .cmp(&a.player.point_difference)
+                .then_with(|| a.player.name.cmp(&b.player.name))
+        });
+        elites
+    }
+
+    /// Number of filled cells.
+    pub fn len(&self) -> usize {
+        self.elites.len()
+    }
+
+    /// Are all the cells empty?
+    pub fn is_empty(&self) -> bool {
+        self.elites.is_empty()
+    }
+
+    /// A random elite, to mutate. None if empty.
+    pub fn choose<R: Rng>(&self, rng: &mut R) -> Option<&Elite<T>> {
+        let elites = self.elites();
+        if elites.is_empty() {
+            return None;
+        }
+        Some(elites[below(rng, elites.len())])
+    }
+
+    /// The more novel (see NoveltyArchive::novelty) of two random elites, to mutate,
+    /// so search spends more time exploring unusual behaviors. None if empty.
+    pub fn choose_novel<R: Rng>(&self, archive: &NoveltyArchive, rng: &mut R) -> Option<&Elite<T>> {
+        let a = self.choose(rng)?;
+        let b = self.choose(rng)?;
+        if archive.novelty(&b.behavior) > archive.novelty(&a.behavior) {
+            Some(b)
+        } else {
+            Some(a)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use patterns;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn roster(names: &[&str]) -> Vec<VecTile> {
+        patterns::classic_roster::<VecTile>(8)
+            .into_iter()
+            .filter(|(n, _)| names.contains(&n.as_str()))
+            .map(|(_, t)| t)
+            .collect()
+    }
+
+    #[test]
+    fn test_behavior() {
+        let opponents = roster(&["Empty"]);
+        let block = &roster(&["Block"])[0];
+        let glider = &roster(&["Glider"])[0];
+
+        // A block against empty space does nothing.
+        let e = evaluate(block, &opponents, 100);
+        assert_eq!(e.behavior.self_period, Some(1));
+        assert_eq!(e.behavior.frontier_speed, 0.0);
+        assert_eq!(e.behavior.contested_width, 0.0);
+
+        // Gliders fill empty space, the frontier moving at c/4.
+        let e = evaluate(glider, &opponents, 100);
+        // On an 8x8 torus, a glider comes back after 32 generations.
+        assert_eq!(e.behavior.self_period, Some(32));
+        assert!((e.behavior.frontier_speed - 0.25).abs() < 0.02);
+        assert_eq!(e.behavior.contested_width, 0.0);
+
+        // Against blocks they crash, leaving debris.
+        let e = evaluate(glider, &roster(&["Block"]), 100);
+        assert!(e.behavior.contested_width > 0.0);
+        assert!(e.behavior.objects >= 1.0);
+        assert!(e.behavior.density > 0.0 && e.behavior.density < 0.5);
+    }
+
+    #[test]
+    fn test_archives() {
+        let opponents = roster(&["Empty", "Block"]);
+        let mut novelty = NoveltyArchive::new(1, 0.5);
+        let mut elites = MapElites::default();
+        for (name, tile) in patterns::classic_roster::<VecTile>(8) {
+            let e = evaluate(&tile, &opponents, 100);
+            novelty.consider(&e.behavior);
+            elites.insert(name, tile, &e);
+        }
+
+        // Still lifes all behave the same, so don't fill the archives.
+        assert!(elites.len() > 1 && elites.len() < patterns::classic_roster::<VecTile>(8).len());
+        assert!(novelty.len() > 1 && novelty.len() <= elites.len() + 1);
+        let e = evaluate(&roster(&["Block"])[0], &opponents, 100);
+        assert_eq!(novelty.novelty(&e.behavior), 0.0);
+
+        let best = elites.elites();
+        for pair in best.windows(2) {
+            assert!(pair[0].player.point_difference >= pair[1].player.point_difference);
+        }
+
+        // Parents are drawn towards behaviors far from the archive.
+        let mut archive = NoveltyArchive::new(1, 0.0);
+        archive.consider(&e.behavior);
+        let mut rng = StdRng::from_seed([7; 32]);
+        let mut block_like = 0;
+        for _ in 0..100 {
+            let chosen = elites.choose_novel(&archive, &mut rng).unwrap();
+            if archive.novelty(&chosen.behavior) == 0.0 {
+                block_like += 1;
+            }
+        }
+        assert!(block_like < 100 / elites.len());
+    }
+}
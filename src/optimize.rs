@@ -0,0 +1,266 @@
+//! Hill climbing and simulated annealing of a single tile against fixed opponents.
+
+use board::Board;
+use game;
+use rand::Rng;
+use random::{below, chance};
+use snapshot::write_tile;
+use std::collections::HashMap;
+use tile::*;
+
+/// How to decide whether to move to a mutated tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// Only accept mutations which don't make fitness worse.
+    HillClimb,
+    /// Also accept worse mutations with probability exp(change / temperature),
+    /// the temperature starting at start_temperature and multiplied by cooling every iteration.
+    Anneal {
+        /// Temperature at the first iteration.
+        start_temperature: f64,
+        /// Factor the temperature is multiplied by every iteration, below 1.
+        cooling: f64,
+    },
+}
+
+/// Settings for optimize.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    /// Length of each match.
+    pub generations: usize,
+    /// Number of mutations to try.
+    pub iterations: usize,
+    /// Whether to hill climb or anneal.
+    pub schedule: Schedule,
+    /// Side of the square of cells a block edit randomizes. Half of mutations are block edits,
+    /// the rest flip a single cell.
+    pub block_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            generations: 1000,
+            iterations: 500,
+            schedule: Schedule::Anneal {
+                start_temperature: 4.0,
+                cooling: 0.99,
+            },
+            block_size: 3,
+        }
+    }
+}
+
+/// One iteration of the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    /// Fitness of the mutated tile tried this iteration.
+    pub fitness: isize,
+    /// Whether the search moved to it.
+    pub accepted: bool,
+    /// Best fitness found so far, including this iteration.
+    pub best_fitness: isize,
+}
+
+/// The result of optimize.
+#[derive(Debug, Clone)]
+pub struct Optimized<T>
+where
+    T: LifeTile,
+{
+    /// The fittest tile found.
+    pub best: T,
+    /// Fitness of best.
+    pub best_fitness: isize,
+    /// Fitness of the tile the search started from.
+    pub start_fitness: isize,
+    /// One entry per iteration.
+    pub trajectory: Vec<Step>,
+    /// Tiles actually played against the opponents, rather than found in the cache.
+    pub evaluations: usize,
+}
+
+/// Fitness of tiles against a fixed set of opponents: the sum, over all opponents,
+/// of the tile's score minus the opponent's score (see game::struggle_board).
+/// Results are cached, since local search often revisits tiles (for example flipping a cell back).
+pub struct Fitness<'a, T>
+where
+    T: LifeTile + 'a,
+{
+    opponents: &'a [T],
+    generations: usize,
+    cache: HashMap<Vec<u8>, isize>,
+    evaluations: usize,
+}
+
+impl<'a, T> Fitness<'a, T>
+where
+    T: LifeTile,
+{
+    /// Play matches of generations against opponents.
+    pub fn new(opponents: &'a [T], generations: usize) -> Fitness<'a, T> {
+        Fitness {
+            opponents,
+            generations,
+            cache: HashMap::new(),
+            evaluations: 0,
+        }
+    }
+
+    /// Fitness of tile, higher is better.
+    pub fn evaluate(&mut self, tile: &T) -> isize {
+        let mut key = vec![];
+        write_tile(&mut key, tile).unwrap();
+        if let Some(fitness) = self.cache.get(&key) {
+            return *fitness;
+        }
+
+        self.evaluations += 1;
+        let fitness = self
+            .opponents
+            .iter()
+            .map(|o| match game::struggle_board(self.generations, tile, o) {
+                Some(b) => {
+                    let (own, other) = b.score();
+                    own - other
+                }
+                None => 0,
+            }).sum();
+        self.cache.insert(key, fitness);
+        fitness
+    }
+
+    /// Tiles played rather than found in the cache so far.
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+}
+
+/// Local search from start for a tile which does well against opponents.
+pub fn optimize<T, R>(start: &T, opponents: &[T], config: &Config, rng: &mut R) -> Optimized<T>
+where
+    T: LifeTile,
+    R: Rng,
+{
+    let mut fitness = Fitness::new(opponents, config.generations);
+    let mut current = start.clone();
+    let mut current_fitness = fitness.evaluate(&current);
+    let start_fitness = current_fitness;
+    let mut best = current.clone();
+    let mut best_fitness = current_fitness;
+    let mut trajectory = Vec::with_capacity(config.iterations);
+
+    for iteration in 0..config.iterations {
+        let candidate = mutate(&current, config.block_size, rng);
+        let candidate_fitness = fitness.evaluate(&candidate);
+        let change = (candidate_fitness - current_fitness) as f64;
+        let accepted = change >= 0.0
+            || match config.schedule {
+                Schedule::HillClimb => false,
+                Schedule::Anneal {
+                    start_temperature,
+                    cooling,
+                } => {
+                    let temperature = start_temperature * cooling.powi(iteration as i32);
+                    temperature > 0.0 && chance(rng, (change / temperature).exp())
+                }
+            };
+
+        if accepted {
+            current = candidate;
+            current_fitness = candidate_fitness;
+            if current_fitness > best_fitness {
+                best = current.clone();
+                best_fitness = current_fitness;
+            }
+        }
+        trajectory.push(Step {
+            fitness: candidate_fitness,
+            accepted,
+            best_fitness,
+        });
+    }
+
+    Optimized {
+        best,
+        best_fitness,
+        start_fitness,
+        trajectory,
+        evaluations: fitness.evaluations(),
+    }
+}
+
+/// A copy of t with either one cell flipped, or a block_size square of cells randomized.
+pub fn mutate<T, R>(t: &T, block_size: usize, rng: &mut R) -> T
+where
+    T: LifeTile,
+    R: Rng,
+{
+    let size = t.size();
+    let mut m = t.clone();
+    let x = below(rng, size);
+    let y = below(rng, size);
+    if block_size > 1 && rng.gen() {
+        // Wraps around, as tiles repeat endlessly.
+        for dy in 0..block_size {
+            for dx in 0..block_size {
+                m.set((x + dx) % size, (y + dy) % size, rng.gen());
+            }
+        }
+    } else {
+        let v = m.get(x, y);
+        m.set(x, y, !v);
+    }
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use patterns;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_optimize() {
+        let mut rng = StdRng::from_seed([10; 32]);
+        let opponents: Vec<VecTile> = patterns::classic_roster(8)
+            .into_iter()
+            .filter(|(name, _)| name == "Glider" || name == "Block")
+            .map(|(_, t)| t)
+            .collect();
+        let start = VecTile::new(8);
+        for schedule in [
+            Schedule::HillClimb,
+            Schedule::Anneal {
+                start_temperature: 2.0,
+                cooling: 0.9,
+            },
+        ].iter()
+        {
+            let config = Config {
+                generations: 100,
+                iterations: 40,
+                schedule: *schedule,
+                block_size: 3,
+            };
+            let result = optimize(&start, &opponents, &config, &mut rng);
+            assert_eq!(result.trajectory.len(), 40);
+            assert!(result.best_fitness >= result.start_fitness);
+            assert_eq!(result.trajectory[39].best_fitness, result.best_fitness);
+            assert_eq!(Fitness::new(&opponents, 100).evaluate(&result.best), result.best_fitness);
+            assert!(result.evaluations <= 41);
+        }
+    }
+
+    #[test]
+    fn test_fitness_cache() {
+        let opponents = vec![VecTile::new(8)];
+        let mut fitness = Fitness::new(&opponents, 50);
+        let mut t = VecTile::new(8);
+        t.set(1, 1, true);
+        let first = fitness.evaluate(&t);
+        assert_eq!(fitness.evaluate(&t.clone()), first);
+        assert_eq!(fitness.evaluations(), 1);
+    }
+}
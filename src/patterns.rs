@@ -0,0 +1,425 @@
+//! A catalog of well-known Life patterns, and a roster of classic players built from them.
+
+use tile::*;
+
+/// What a pattern does when evolved alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Never changes.
+    StillLife,
+    /// Returns to its shape every period generations, in place.
+    Oscillator {
+        /// Generations to return to its shape.
+        period: usize,
+    },
+    /// Returns to its shape every period generations, moved by velocity cells (x, y),
+    /// as stored (before any orientation is applied).
+    Spaceship {
+        /// Generations to return to its shape.
+        period: usize,
+        /// Cells moved (x, y) each period.
+        velocity: (isize, isize),
+    },
+    /// Periodically emits spaceships.
+    Gun {
+        /// Generations between spaceships.
+        period: usize,
+    },
+    /// A still life which destroys gliders hitting it.
+    Eater,
+    /// Moves in direction heading, as stored, leaving debris behind.
+    Puffer {
+        /// Direction (x, y) it moves in.
+        heading: (isize, isize),
+    },
+}
+
+/// A named pattern of cells, as in the plaintext format: rows of '.' (dead) and 'O' (alive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pattern {
+    /// Its usual name, for example "glider".
+    pub name: &'static str,
+    /// What it does.
+    pub kind: Kind,
+    /// Its cells, top row first.
+    pub rows: &'static [&'static str],
+}
+
+/// One of the 8 ways to place a pattern: mirrored (x -> -x) or not,
+/// then rotated by quarter turns from +x towards +y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// Mirrored before rotating?
+    pub mirror: bool,
+    /// Quarter turns, 0 to 3.
+    pub quarter_turns: usize,
+}
+
+impl Orientation {
+    /// As stored.
+    pub const IDENTITY: Orientation = Orientation {
+        mirror: false,
+        quarter_turns: 0,
+    };
+
+    /// All 8 orientations.
+    pub fn all() -> Vec<Orientation> {
+        let mut all = vec![];
+        for mirror in [false, true].iter() {
+            for quarter_turns in 0..4 {
+                all.push(Orientation {
+                    mirror: *mirror,
+                    quarter_turns,
+                });
+            }
+        }
+        all
+    }
+
+    /// Where cell (x, y) of a width x height pattern ends up,
+    /// keeping the result's top left corner at (0, 0).
+    pub fn apply(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        let (mut x, mut y, mut w, mut h) = (x, y, width, height);
+        if self.mirror {
+            x = w - 1 - x;
+        }
+        for _ in 0..self.quarter_turns % 4 {
+            let rotated = (h - 1 - y, x);
+            x = rotated.0;
+            y = rotated.1;
+            std::mem::swap(&mut w, &mut h);
+        }
+        (x, y)
+    }
+
+    /// A direction, such as a spaceship's velocity, after applying this orientation.
+    pub fn apply_vector(&self, v: (isize, isize)) -> (isize, isize) {
+        let (mut x, mut y) = v;
+        if self.mirror {
+            x = -x;
+        }
+        for _ in 0..self.quarter_turns % 4 {
+            let rotated = (-y, x);
+            x = rotated.0;
+            y = rotated.1;
+        }
+        (x, y)
+    }
+}
+
+impl Pattern {
+    /// Width and height as stored.
+    pub fn dimensions(&self) -> (usize, usize) {
+        let width = self.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        (width, self.rows.len())
+    }
+
+    /// Width and height after applying orientation.
+    pub fn oriented_dimensions(&self, orientation: Orientation) -> (usize, usize) {
+        let (w, h) = self.dimensions();
+        if orientation.quarter_turns.is_multiple_of(2) {
+            (w, h)
+        } else {
+            (h, w)
+        }
+    }
+
+    /// Live cells, as stored.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = vec![];
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == 'O' {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Set the pattern's live cells in tile, with its top left corner at (x, y) after orientation.
+    /// Wraps around the tile's edges, as the tile repeats endlessly anyway.
+    /// Other cells are left as they were.
+    pub fn stamp<T>(&self, tile: &mut T, x: usize, y: usize, orientation: Orientation)
+    where
+        T: LifeTile,
+    {
+        let size = tile.size();
+        let (w, h) = self.dimensions();
+        for (cx, cy) in self.cells() {
+            let (ox, oy) = orientation.apply(cx, cy, w, h);
+            tile.set((x + ox) % size, (y + oy) % size, true);
+        }
+    }
+
+    /// Does the pattern fit in a size x size tile without touching itself across the tile's edges?
+    pub fn fits(&self, size: usize) -> bool {
+        let (w, h) = self.dimensions();
+        w.max(h) + 2 <= size
+    }
+}
+
+/// Every pattern in the catalog.
+pub const CATALOG: &[Pattern] = &[
+    // Still lifes
+    Pattern {
+        name: "Block",
+        kind: Kind::StillLife,
+        rows: &["OO", "OO"],
+    },
+    Pattern {
+        name: "Beehive",
+        kind: Kind::StillLife,
+        rows: &[".OO.", "O..O", ".OO."],
+    },
+    Pattern {
+        name: "Loaf",
+        kind: Kind::StillLife,
+        rows: &[".OO.", "O..O", ".O.O", "..O."],
+    },
+    Pattern {
+        name: "Boat",
+        kind: Kind::StillLife,
+        rows: &["OO.", "O.O", ".O."],
+    },
+    Pattern {
+        name: "Tub",
+        kind: Kind::StillLife,
+        rows: &[".O.", "O.O", ".O."],
+    },
+    // Oscillators
+    Pattern {
+        name: "Blinker",
+        kind: Kind::Oscillator { period: 2 },
+        rows: &["OOO"],
+    },
+    Pattern {
+        name: "Toad",
+        kind: Kind::Oscillator { period: 2 },
+        rows: &[".OOO", "OOO."],
+    },
+    Pattern {
+        name: "Beacon",
+        kind: Kind::Oscillator { period: 2 },
+        rows: &["OO..", "OO..", "..OO", "..OO"],
+    },
+    Pattern {
+        name: "Pulsar",
+        kind: Kind::Oscillator { period: 3 },
+        rows: &[
+            "..OOO...OOO..",
+            ".............",
+            "O....O.O....O",
+            "O....O.O....O",
+            "O....O.O....O",
+            "..OOO...OOO..",
+            ".............",
+            "..OOO...OOO..",
+            "O....O.O....O",
+            "O....O.O....O",
+            "O....O.O....O",
+            ".............",
+            "..OOO...OOO..",
+        ],
+    },
+    // Spaceships
+    Pattern {
+        name: "Glider",
+        kind: Kind::Spaceship {
+            period: 4,
+            velocity: (1, 1),
+        },
+        rows: &[".O.", "..O", "OOO"],
+    },
+    Pattern {
+        name: "Lwss",
+        kind: Kind::Spaceship {
+            period: 4,
+            velocity: (-2, 0),
+        },
+        rows: &[".O..O", "O....", "O...O", "OOOO."],
+    },
+    Pattern {
+        name: "Mwss",
+        kind: Kind::Spaceship {
+            period: 4,
+            velocity: (-2, 0),
+        },
+        rows: &["...O..", ".O...O", "O.....", "O....O", "OOOOO."],
+    },
+    Pattern {
+        name: "Hwss",
+        kind: Kind::Spaceship {
+            period: 4,
+            velocity: (-2, 0),
+        },
+        rows: &["...OO..", ".O....O", "O......", "O.....O", "OOOOOO."],
+    },
+    // Guns
+    Pattern {
+        name: "Gosper glider gun",
+        kind: Kind::Gun { period: 30 },
+        rows: &[
+            "........................O...........",
+            "......................O.O...........",
+            "............OO......OO............OO",
+            "...........O...O....OO............OO",
+            "OO........O.....O...OO..............",
+            "OO........O...O.OO....O.O...........",
+            "..........O.....O.......O...........",
+            "...........O...O....................",
+            "............OO......................",
+        ],
+    },
+    // Eaters
+    Pattern {
+        name: "Eater",
+        kind: Kind::Eater,
+        rows: &["OO..", "O.O.", "..O.", "..OO"],
+    },
+    // Puffers
+    Pattern {
+        name: "Puffer 2",
+        kind: Kind::Puffer { heading: (0, -1) },
+        rows: &[
+            ".OOO...........OOO",
+            "O..O..........O..O",
+            "...O....OOO......O",
+            "...O....O..O.....O",
+            "..O....O........O.",
+        ],
+    },
+];
+
+/// The catalog pattern with this name.
+pub fn find(name: &str) -> Option<&'static Pattern> {
+    CATALOG.iter().find(|p| p.name == name)
+}
+
+/// Named baseline players for size x size tiles: an empty tile,
+/// and every catalog pattern which fits, with spaceships and puffers heading +x (towards the enemy).
+pub fn classic_roster<T>(size: usize) -> Vec<(String, T)>
+where
+    T: LifeTile,
+{
+    let mut roster = vec![("Empty".to_string(), T::new(size))];
+    for pattern in CATALOG.iter().filter(|p| p.fits(size)) {
+        let heading = match pattern.kind {
+            Kind::Spaceship { velocity, .. } => Some(velocity),
+            Kind::Puffer { heading } => Some(heading),
+            _ => None,
+        };
+        let orientation = match heading {
+            Some(heading) => Orientation::all()
+                .into_iter()
+                .find(|o| {
+                    let v = o.apply_vector(heading);
+                    v.0 > 0 && v.1 >= 0
+                }).unwrap(),
+            None => Orientation::IDENTITY,
+        };
+        let mut t = T::new(size);
+        pattern.stamp(&mut t, 1, 1, orientation);
+        roster.push((pattern.name.to_string(), t));
+    }
+    roster
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn population(t: &BitTile) -> usize {
+        let size = t.size();
+        (0..size)
+            .map(|y| (0..size).filter(|x| t.get(*x, y)).count())
+            .sum()
+    }
+
+    fn evolve(t: &BitTile, generations: usize) -> BitTile {
+        let mut t = t.clone();
+        for _ in 0..generations {
+            t = t.next_generation(&t, &t);
+        }
+        t
+    }
+
+    // Each pattern does what its kind says, alone in a tile big enough not to interact with itself.
+    #[test]
+    fn test_catalog_kinds() {
+        let size = 100;
+        for pattern in CATALOG.iter() {
+            let mut t = BitTile::new(size);
+            pattern.stamp(&mut t, 40, 40, Orientation::IDENTITY);
+            match pattern.kind {
+                Kind::StillLife | Kind::Eater => {
+                    assert_eq!(t.self_period(10), Some(1), "{}", pattern.name)
+                }
+                Kind::Oscillator { period } => {
+                    assert_eq!(t.self_period(10), Some(period), "{}", pattern.name)
+                }
+                Kind::Spaceship { period, velocity } => {
+                    let mut moved = BitTile::new(size);
+                    let x = (40 + velocity.0) as usize;
+                    let y = (40 + velocity.1) as usize;
+                    pattern.stamp(&mut moved, x, y, Orientation::IDENTITY);
+                    assert_eq!(evolve(&t, period), moved, "{}", pattern.name);
+                }
+                Kind::Gun { period } => {
+                    // Each period adds a 5 cell glider.
+                    let later = evolve(&t, 2 * period);
+                    assert_eq!(population(&later), population(&t) + 10, "{}", pattern.name);
+                    assert_eq!(
+                        population(&evolve(&later, period)),
+                        population(&later) + 5,
+                        "{}",
+                        pattern.name
+                    );
+                }
+                Kind::Puffer { .. } => {
+                    let later = evolve(&t, 60);
+                    assert!(population(&evolve(&later, 60)) > population(&later));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_orientation() {
+        let glider = find("Glider").unwrap();
+        let all = Orientation::all();
+        assert_eq!(all.len(), 8);
+        // Keyed by cell contents, sorted so dedup removes every duplicate.
+        let mut tiles: Vec<Vec<bool>> = all
+            .iter()
+            .map(|o| {
+                let mut t = BitTile::new(8);
+                glider.stamp(&mut t, 2, 2, *o);
+                (0..64).map(|i| t.get(i % 8, i / 8)).collect()
+            }).collect();
+        tiles.sort();
+        tiles.dedup();
+        // A glider has no symmetry, so all 8 orientations differ.
+        assert_eq!(tiles.len(), 8);
+
+        // Oriented gliders move in their oriented velocity.
+        for o in all.iter() {
+            let mut t = BitTile::new(20);
+            glider.stamp(&mut t, 8, 8, *o);
+            let (vx, vy) = o.apply_vector((1, 1));
+            let mut moved = BitTile::new(20);
+            glider.stamp(&mut moved, (8 + vx) as usize, (8 + vy) as usize, *o);
+            assert_eq!(evolve(&t, 4), moved);
+        }
+    }
+
+    #[test]
+    fn test_classic_roster() {
+        let roster = classic_roster::<VecTile>(8);
+        let names: Vec<&str> = roster.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names[0], "Empty");
+        assert!(names.contains(&"Glider") && names.contains(&"Lwss"));
+        assert!(!names.contains(&"Pulsar"));
+        assert!(classic_roster::<VecTile>(40).len() == CATALOG.len() + 1);
+    }
+}
@@ -0,0 +1,40 @@
+//! Tiles entered in a tournament.
+
+use tile::*;
+
+/// A tile entered in a tournament, and its results so far.
+#[derive(Debug, Clone)]
+pub struct Player<T>
+where
+    T: LifeTile,
+{
+    /// The player's design.
+    pub tile: T,
+    /// Name in tables and replays.
+    pub name: String,
+    /// Matches won.
+    pub wins: usize,
+    /// Matches lost.
+    pub losses: usize,
+    /// Total of own score minus opponent's score.
+    pub point_difference: isize,
+    /// Never removed from the tournament, for baseline players.
+    pub keep: bool,
+}
+
+impl<T> Player<T>
+where
+    T: LifeTile,
+{
+    /// A player with no results yet.
+    pub fn new(tile: T, name: String, keep: bool) -> Player<T> {
+        Player {
+            tile,
+            name,
+            wins: 0,
+            losses: 0,
+            point_difference: 0,
+            keep,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Random draws for search and tile generation.
+//!
+//! Everything is drawn from u32s, since rand_core 0.2's block RNGs (such as StdRng and thread_rng)
+//! can make misaligned reads for u64s, which panic in debug builds.
+//! Use these rather than gen::<f64>() or gen_range on usize.
+
+use rand::Rng;
+
+/// Uniform in [0, 1).
+pub(crate) fn unit<R: Rng>(rng: &mut R) -> f64 {
+    f64::from(rng.gen::<u32>()) / (f64::from(u32::MAX) + 1.0)
+}
+
+/// True with probability p.
+pub(crate) fn chance<R: Rng>(rng: &mut R, p: f64) -> bool {
+    unit(rng) < p
+}
+
+/// Uniform in 0..n.
+pub(crate) fn below<R: Rng>(rng: &mut R, n: usize) -> usize {
+    rng.gen_range(0, n as u32) as usize
+}
@@ -0,0 +1,249 @@
+//! A deliberately simple simulator to validate the optimized boards against.
+
+use board::{Board, VecBoard};
+use std::fmt;
+use tile::*;
+
+/// A slow but simple simulation of the 2 player world, for checking VecBoard against.
+/// Simulates every cell of a strip of tiles around x = 0 explicitly,
+/// with cells beyond the strip taken from the backgrounds, each evolved alone on a torus.
+/// Changes spread at most one cell per generation, so this is exact for as many generations
+/// as the strip extends beyond x = 0 in cells.
+/// Shares no simulation code with LifeTile or VecBoard.
+#[derive(Debug, Clone)]
+pub struct ReferenceBoard {
+    size: usize,
+    // Tiles in the strip each side of x = 0: it covers tiles -half_width..half_width.
+    half_width: isize,
+    // Row major, size rows of (2 * half_width * size) cells.
+    cells: Vec<bool>,
+    a: Vec<bool>,
+    b: Vec<bool>,
+}
+
+impl ReferenceBoard {
+    /// a fills x < 0 and b fills x >= 0, placed as given (so b should already be mirrored).
+    /// Exact for at least `generations` generations.
+    pub fn new(a: &dyn LifeTileSrc, b: &dyn LifeTileSrc, generations: usize) -> ReferenceBoard {
+        let size = a.size();
+        assert!(b.size() == size);
+        let half_width = (generations / size + 2) as isize;
+        let cells_of = |t: &dyn LifeTileSrc| {
+            let mut cells = vec![false; size * size];
+            for y in 0..size {
+                for x in 0..size {
+                    cells[y * size + x] = t.get(x, y);
+                }
+            }
+            cells
+        };
+        let mut board = ReferenceBoard {
+            size,
+            half_width,
+            cells: vec![],
+            a: cells_of(a),
+            b: cells_of(b),
+        };
+        let width = board.width();
+        board.cells = (0..size * width)
+            .map(|i| board.background_cell(i % width, i / width))
+            .collect();
+        board
+    }
+
+    /// Is cell (x, y) of the tile at tile_x alive?
+    pub fn get(&self, tile_x: isize, x: usize, y: usize) -> bool {
+        self.cell(tile_x * self.size as isize + x as isize, y)
+    }
+
+    /// Tiles each side of x = 0 which are simulated explicitly.
+    pub fn half_width(&self) -> isize {
+        self.half_width
+    }
+
+    /// Advance one generation.
+    pub fn step(&mut self) {
+        let size = self.size;
+        let width = self.width();
+        let origin = self.half_width * size as isize;
+        let cells = (0..size * width)
+            .map(|i| {
+                let x = (i % width) as isize - origin;
+                let y = (i / width) as isize;
+                rule(|dx, dy| self.cell(x + dx, wrap(y + dy, size)))
+            }).collect();
+        let a = step_torus(&self.a, size);
+        let b = step_torus(&self.b, size);
+        self.cells = cells;
+        self.a = a;
+        self.b = b;
+    }
+
+    fn width(&self) -> usize {
+        2 * self.half_width as usize * self.size
+    }
+
+    // The cell at absolute x, anywhere on the infinite line.
+    fn cell(&self, x: isize, y: usize) -> bool {
+        let origin = self.half_width * self.size as isize;
+        let i = x + origin;
+        if i < 0 || i >= self.width() as isize {
+            let background = if x < 0 { &self.a } else { &self.b };
+            background[y * self.size + wrap(x, self.size)]
+        } else {
+            self.cells[y * self.width() + i as usize]
+        }
+    }
+
+    // The cell at index x of the strip at generation 0, before anything has spread.
+    fn background_cell(&self, x: usize, y: usize) -> bool {
+        let size = self.size;
+        let background = if x < self.half_width as usize * size {
+            &self.a
+        } else {
+            &self.b
+        };
+        background[y * size + x % size]
+    }
+}
+
+// Conway's rule for the cell live(0, 0), where live(dx, dy) is the cell offset by (dx, dy).
+fn rule<F>(live: F) -> bool
+where
+    F: Fn(isize, isize) -> bool,
+{
+    let mut count = 0;
+    for dy in -1isize..=1 {
+        for dx in -1isize..=1 {
+            if (dx, dy) != (0, 0) && live(dx, dy) {
+                count += 1;
+            }
+        }
+    }
+    if live(0, 0) {
+        count == 2 || count == 3
+    } else {
+        count == 3
+    }
+}
+
+// Wrap v into 0..size.
+fn wrap(v: isize, size: usize) -> usize {
+    let size = size as isize;
+    ((v % size + size) % size) as usize
+}
+
+// Evolve a size x size torus of cells by one generation.
+fn step_torus(cells: &[bool], size: usize) -> Vec<bool> {
+    (0..size * size)
+        .map(|i| {
+            let (x, y) = ((i % size) as isize, (i / size) as isize);
+            rule(|dx, dy| cells[wrap(y + dy, size) * size + wrap(x + dx, size)])
+        }).collect()
+}
+
+/// Where a board first differed from the reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// First generation the board differed at.
+    pub generation: usize,
+    /// Lowest x of a tile which differed.
+    pub tile_x: isize,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "board differs from reference at generation {}, tile {}",
+            self.generation, self.tile_x
+        )
+    }
+}
+
+/// Play a match on a VecBoard and a ReferenceBoard side by side,
+/// comparing every tile of the strip after every generation.
+/// tile_b is mirrored, as in game::struggle_board.
+/// Returns the board (None for a convergence draw, where checking stops), or the first divergence.
+pub fn verify_board<T>(
+    generations: usize,
+    tile_a: &dyn LifeTileSrc,
+    tile_b: &dyn LifeTileSrc,
+) -> Result<Option<VecBoard<T>>, Divergence>
+where
+    T: LifeTile,
+{
+    let a = T::copy_from(tile_a);
+    let b = T::copy_from(tile_b).mirror();
+    let mut reference = ReferenceBoard::new(&a, &b, generations);
+    let mut board = match VecBoard::new(a, b) {
+        Some(board) => board,
+        None => return Ok(None),
+    };
+
+    for generation in 0..=generations {
+        if let Some(tile_x) = first_difference(&board, &reference) {
+            return Err(Divergence { generation, tile_x });
+        }
+        if generation < generations {
+            if !board.step() {
+                return Ok(None);
+            }
+            reference.step();
+        }
+    }
+    Ok(Some(board))
+}
+
+// The lowest x of any tile in the reference's strip which differs on board.
+fn first_difference<T>(board: &VecBoard<T>, reference: &ReferenceBoard) -> Option<isize>
+where
+    T: LifeTile,
+{
+    let size = board.tile_size();
+    (-reference.half_width()..reference.half_width()).find(|&tile_x| {
+        let t = board.tile_at(tile_x);
+        (0..size).any(|y| (0..size).any(|x| t.get(x, y) != reference.get(tile_x, x, y)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generate::Generator;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_matches_reference() {
+        let mut rng = StdRng::from_seed([9; 32]);
+        for size in [3, 5, 8, 13].iter() {
+            for _ in 0..5 {
+                let random = Generator::Uniform { density: 0.3 };
+                let a: VecTile = random.generate(*size, &mut rng);
+                let b: VecTile = random.generate(*size, &mut rng);
+                if let Err(d) = verify_board::<VecTile>(150, &a, &b) {
+                    panic!("size {}: {}", size, d);
+                }
+                if let Err(d) = verify_board::<ByteTile>(150, &a, &b) {
+                    panic!("size {}: {}", size, d);
+                }
+            }
+        }
+    }
+
+    // The reference should notice when a board is wrong.
+    #[test]
+    fn test_detects_divergence() {
+        let mut a = VecTile::new(5);
+        a.set(2, 1, true);
+        a.set(2, 2, true);
+        a.set(2, 3, true);
+        let b = VecTile::new(5);
+        let board = VecBoard::new(a.clone(), b.clone()).unwrap();
+        let mut reference = ReferenceBoard::new(&a, &b, 10);
+        assert_eq!(first_difference(&board, &reference), None);
+        reference.step();
+        assert_eq!(first_difference(&board, &reference), Some(-reference.half_width()));
+    }
+}
@@ -0,0 +1,638 @@
+//! Recording matches to files, and replaying them to check they still play out the same.
+
+use board::VecBoard;
+use engine::{Engine, EngineBoard};
+use game;
+use snapshot::{read_tile, tile_bytes, write_tile};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use termination::{Outcome, StopReason, TerminationPolicy};
+use tile::*;
+
+/// Version of the simulator which recorded a replay.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The only rule currently supported: Conway's Game of Life.
+pub const RULE: &str = "B3/S23";
+
+const HEADER: &str = "life_struggle replay";
+
+/// Everything needed to reproduce a match, and its recorded result.
+/// Saved as a small text file, one `key value` pair per line, with tiles as hex encoded packed bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay<T>
+where
+    T: LifeTile,
+{
+    /// ENGINE_VERSION of the simulator which recorded it.
+    pub engine_version: String,
+    /// Rule the match was played with, always RULE.
+    pub rule: String,
+    /// Full length of the match.
+    pub generations: usize,
+    /// Player a's tile.
+    pub tile_a: T,
+    /// As given by the player: the board mirrors it as usual.
+    pub tile_b: T,
+    /// Whether tile_b is flipped along y (see LifeTile::mirror_over_x) before the match.
+    pub b_mirror_over_x: bool,
+    /// Rows tile_b is shifted by along y, before mirroring.
+    pub b_offset_y: usize,
+    /// When the match could stop early: the default plays every generation.
+    pub policy: TerminationPolicy,
+    /// None for a convergence draw. For a match which stopped early, see Outcome::score.
+    pub score: Option<(isize, isize)>,
+    /// Why the match stopped, and at which generation.
+    pub reason: StopReason,
+    /// Generation the match stopped at (see Outcome::generation).
+    /// None in replays from before it was recorded.
+    pub stopped_at: Option<usize>,
+    /// Widest the contested region got, in tiles (see Outcome::max_width).
+    /// None in replays from before it was recorded.
+    pub widest: Option<usize>,
+    /// Hash of the board when it stopped.
+    pub board_hash: u64,
+}
+
+/// How a rerun of a replay differed from the recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Score in the replay.
+    pub recorded_score: Option<(isize, isize)>,
+    /// Score when replayed.
+    pub score: Option<(isize, isize)>,
+    /// Board hash in the replay.
+    pub recorded_hash: u64,
+    /// Board hash when replayed.
+    pub hash: u64,
+    /// Why the match stopped in the replay.
+    pub recorded_reason: StopReason,
+    /// Why the match stopped when replayed.
+    pub reason: StopReason,
+    /// Generation the match stopped at in the replay, if recorded.
+    pub recorded_stopped_at: Option<usize>,
+    /// Generation the match stopped at when replayed.
+    pub stopped_at: usize,
+    /// Widest the contested region got in the replay, if recorded.
+    pub recorded_widest: Option<usize>,
+    /// Widest the contested region got when replayed.
+    pub widest: usize,
+    /// Version of the simulator which recorded the replay.
+    pub recorded_engine_version: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let recorded = |v: Option<usize>| v.map_or("unknown".to_string(), |v| v.to_string());
+        write!(
+            f,
+            "score {:?} (recorded {:?}), {} at generation {} (recorded {} at {}), \
+             widest {} (recorded {}), board hash {:016x} (recorded {:016x}), recorded by engine {}",
+            self.score,
+            self.recorded_score,
+            self.reason,
+            self.stopped_at,
+            self.recorded_reason,
+            recorded(self.recorded_stopped_at),
+            self.widest,
+            recorded(self.recorded_widest),
+            self.hash,
+            self.recorded_hash,
+            self.recorded_engine_version
+        )
+    }
+}
+
+impl<T> Replay<T>
+where
+    T: LifeTile,
+{
+    /// Play a match and record it.
+    /// Returns the replay and the final board, as from game::struggle_board.
+    pub fn record(
+        generations: usize,
+        tile_a: &T,
+        tile_b: &T,
+        b_mirror_over_x: bool,
+        b_offset_y: usize,
+    ) -> (Replay<T>, Option<VecBoard<VecTile>>) {
+        let policy = TerminationPolicy::default();
+        let mut replay = Replay::unplayed(
+            generations,
+            tile_a,
+            tile_b,
+            b_mirror_over_x,
+            b_offset_y,
+            policy,
+        );
+        let outcome = replay.outcome();
+        replay.board_hash = board_hash(&outcome.board, outcome.generation);
+        replay.set_outcome(&outcome);
+        (replay, outcome.board)
+    }
+
+    /// Like record, but simulating with the given engine.
+    /// The replay is the same whichever engine is used.
+    pub fn record_with(
+        engine: Engine,
+        generations: usize,
+        tile_a: &T,
+        tile_b: &T,
+        b_mirror_over_x: bool,
+        b_offset_y: usize,
+    ) -> (Replay<T>, Option<EngineBoard>) {
+        let policy = TerminationPolicy::default();
+        let (replay, outcome) = Replay::record_until(
+            engine,
+            generations,
+            tile_a,
+            tile_b,
+            b_mirror_over_x,
+            b_offset_y,
+            policy,
+        );
+        (replay, outcome.board)
+    }
+
+    /// Like record_with, but stopping early according to policy, which is recorded too.
+    pub fn record_until(
+        engine: Engine,
+        generations: usize,
+        tile_a: &T,
+        tile_b: &T,
+        b_mirror_over_x: bool,
+        b_offset_y: usize,
+        policy: TerminationPolicy,
+    ) -> (Replay<T>, Outcome<EngineBoard>) {
+        let mut replay = Replay::unplayed(
+            generations,
+            tile_a,
+            tile_b,
+            b_mirror_over_x,
+            b_offset_y,
+            policy,
+        );
+        let outcome = replay.outcome_with(engine);
+        replay.board_hash = outcome
+            .board
+            .as_ref()
+            .map_or(0, |x| x.hash(outcome.generation));
+        replay.set_outcome(&outcome);
+        (replay, outcome)
+    }
+
+    /// Rerun the match, without checking the result.
+    /// Returns the board when it stopped.
+    pub fn play(&self) -> Option<VecBoard<VecTile>> {
+        self.outcome().board
+    }
+
+    /// Like play, but simulating with the given engine.
+    pub fn play_with(&self, engine: Engine) -> Option<EngineBoard> {
+        self.outcome_with(engine).board
+    }
+
+    /// Rerun the match under its policy, without checking the result.
+    pub fn outcome(&self) -> Outcome<VecBoard<VecTile>> {
+        game::struggle_board_until(
+            self.generations,
+            &self.tile_a,
+            &self.oriented_b(),
+            self.policy,
+        )
+    }
+
+    /// Like outcome, but simulating with the given engine.
+    pub fn outcome_with(&self, engine: Engine) -> Outcome<EngineBoard> {
+        let b = self.oriented_b();
+        game::struggle_engine_until(engine, self.generations, &self.tile_a, &b, self.policy)
+    }
+
+    // Record the result of playing the match.
+    fn set_outcome<B>(&mut self, outcome: &Outcome<B>) {
+        self.score = outcome.board.as_ref().map(|_| outcome.score);
+        self.reason = outcome.reason;
+        self.stopped_at = Some(outcome.generation);
+        self.widest = Some(outcome.max_width);
+    }
+
+    // A replay of a match which hasn't been played yet, so has no result.
+    fn unplayed(
+        generations: usize,
+        tile_a: &T,
+        tile_b: &T,
+        b_mirror_over_x: bool,
+        b_offset_y: usize,
+        policy: TerminationPolicy,
+    ) -> Replay<T> {
+        Replay {
+            engine_version: ENGINE_VERSION.to_string(),
+            rule: RULE.to_string(),
+            generations,
+            tile_a: tile_a.clone(),
+            tile_b: tile_b.clone(),
+            b_mirror_over_x,
+            b_offset_y,
+            policy,
+            score: None,
+            reason: StopReason::Completed,
+            stopped_at: None,
+            widest: None,
+            board_hash: 0,
+        }
+    }
+
+    // tile_b as played.
+    fn oriented_b(&self) -> T {
+        let b = self.tile_b.shift_y(self.b_offset_y);
+        if self.b_mirror_over_x {
+            b.mirror_over_x()
+        } else {
+            b
+        }
+    }
+
+    /// Rerun the match and check it ends the same way as recorded:
+    /// with the same score and board, for the same reason, at the same generation and width.
+    /// A divergence means the simulator's behavior has changed.
+    pub fn verify(&self) -> Result<(), Box<Divergence>> {
+        let outcome = self.outcome();
+        let score = outcome.board.as_ref().map(|_| outcome.score);
+        let hash = board_hash(&outcome.board, outcome.generation);
+        if score == self.score
+            && hash == self.board_hash
+            && outcome.reason == self.reason
+            && self.stopped_at.is_none_or(|g| g == outcome.generation)
+            && self.widest.is_none_or(|w| w == outcome.max_width)
+        {
+            Ok(())
+        } else {
+            Err(Box::new(Divergence {
+                recorded_score: self.score,
+                score,
+                recorded_hash: self.board_hash,
+                hash,
+                recorded_reason: self.reason,
+                reason: outcome.reason,
+                recorded_stopped_at: self.stopped_at,
+                stopped_at: outcome.generation,
+                recorded_widest: self.widest,
+                widest: outcome.max_width,
+                recorded_engine_version: self.engine_version.clone(),
+            }))
+        }
+    }
+
+    /// Write to path in the text format.
+    pub fn save<Q>(&self, path: Q) -> io::Result<()>
+    where
+        Q: AsRef<Path>,
+    {
+        let score = match self.score {
+            Some((a, b)) => format!("{} {}", a, b),
+            None => "draw".to_string(),
+        };
+        let policy = &self.policy;
+        let text = format!(
+            "{}\nengine_version {}\nrule {}\ngenerations {}\nsize {}\ntile_a {}\ntile_b {}\n\
+             b_mirror_over_x {}\nb_offset_y {}\nscore {}\nboard_hash {:016x}\n\
+             stable {}\ncapture_threshold {}\ncycle_window {}\nmax_width {}\non_max_width {}\n\
+             reason {}\nstopped_at {}\nwidest {}\n",
+            HEADER,
+            self.engine_version,
+            self.rule,
+            self.generations,
+            self.tile_a.size(),
+            tile_to_hex(&self.tile_a),
+            tile_to_hex(&self.tile_b),
+            self.b_mirror_over_x,
+            self.b_offset_y,
+            score,
+            self.board_hash,
+            policy.stable,
+            optional_to_string(policy.capture_threshold),
+            policy.cycle_window,
+            optional_to_string(policy.max_width),
+            policy.on_max_width,
+            self.reason,
+            optional_to_string(self.stopped_at),
+            optional_to_string(self.widest)
+        );
+        fs::write(path, text)
+    }
+
+    /// Read a file written by save, by this or an earlier version.
+    pub fn load<Q>(path: Q) -> io::Result<Replay<T>>
+    where
+        Q: AsRef<Path>,
+    {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid_data("not a replay"));
+        }
+
+        let mut field = |key: &str| -> io::Result<String> {
+            let line = lines.next().unwrap_or("");
+            let mut parts = line.splitn(2, ' ');
+            if parts.next() != Some(key) {
+                return Err(invalid_data(&format!("expected {}", key)));
+            }
+            Ok(parts.next().unwrap_or("").to_string())
+        };
+
+        let engine_version = field("engine_version")?;
+        let rule = field("rule")?;
+        if rule != RULE {
+            return Err(invalid_data("unsupported rule"));
+        }
+        let generations = parse(&field("generations")?)?;
+        let size = parse(&field("size")?)?;
+        let tile_a = tile_from_hex(&field("tile_a")?, size)?;
+        let tile_b = tile_from_hex(&field("tile_b")?, size)?;
+        let b_mirror_over_x = parse(&field("b_mirror_over_x")?)?;
+        let b_offset_y = parse(&field("b_offset_y")?)?;
+        let score = field("score")?;
+        let score = if score == "draw" {
+            None
+        } else {
+            let mut parts = score.split(' ');
+            let a = parse(parts.next().unwrap_or(""))?;
+            let b = parse(parts.next().unwrap_or(""))?;
+            Some((a, b))
+        };
+        let board_hash = u64::from_str_radix(&field("board_hash")?, 16)
+            .map_err(|_| invalid_data("bad board_hash"))?;
+
+        // Added later, so missing from older replays, which played every generation.
+        let mut extra: HashMap<&str, &str> = [
+            ("stable", "false"),
+            ("capture_threshold", "none"),
+            ("cycle_window", "0"),
+            ("max_width", "none"),
+            ("on_max_width", "adjudicate"),
+            ("stopped_at", "none"),
+            ("widest", "none"),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        extra.extend(lines.map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            (parts.next().unwrap_or(""), parts.next().unwrap_or(""))
+        }));
+        let policy = TerminationPolicy {
+            stable: parse(extra["stable"])?,
+            capture_threshold: parse_optional(extra["capture_threshold"])?,
+            cycle_window: parse(extra["cycle_window"])?,
+            max_width: parse_optional(extra["max_width"])?,
+            on_max_width: extra["on_max_width"]
+                .parse()
+                .map_err(|e: String| invalid_data(&e))?,
+        };
+        let completed = if score.is_some() {
+            StopReason::Completed
+        } else {
+            StopReason::ConvergenceDraw
+        };
+        let reason = match extra.get("reason") {
+            Some(reason) => reason.parse().map_err(|e: String| invalid_data(&e))?,
+            None => completed,
+        };
+
+        Ok(Replay {
+            engine_version,
+            rule,
+            generations,
+            tile_a,
+            tile_b,
+            b_mirror_over_x,
+            b_offset_y,
+            policy,
+            score,
+            reason,
+            stopped_at: parse_optional(extra["stopped_at"])?,
+            widest: parse_optional(extra["widest"])?,
+            board_hash,
+        })
+    }
+}
+
+/// Hash of the board's snapshot (see Snapshot::hash), or 0 for a convergence draw.
+pub fn board_hash(board: &Option<VecBoard<VecTile>>, generation: usize) -> u64 {
+    match *board {
+        Some(ref x) => x.snapshot(generation).hash(),
+        None => 0,
+    }
+}
+
+fn tile_to_hex<T>(t: &T) -> String
+where
+    T: LifeTile,
+{
+    let mut bytes = vec![];
+    write_tile(&mut bytes, t).unwrap();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn tile_from_hex<T>(hex: &str, size: usize) -> io::Result<T>
+where
+    T: LifeTile,
+{
+    if tile_bytes(size).map(|n| n * 2) != Some(hex.len()) || !hex.is_ascii() {
+        return Err(invalid_data("bad tile"));
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| invalid_data("bad tile"))?;
+    read_tile(&mut &bytes[..], size)
+}
+
+fn parse<F>(s: &str) -> io::Result<F>
+where
+    F: ::std::str::FromStr,
+{
+    s.parse()
+        .map_err(|_| invalid_data(&format!("bad value: {}", s)))
+}
+
+// "none" for None.
+fn optional_to_string<V>(v: Option<V>) -> String
+where
+    V: ToString,
+{
+    v.map_or("none".to_string(), |v| v.to_string())
+}
+
+fn parse_optional<F>(s: &str) -> io::Result<Option<F>>
+where
+    F: ::std::str::FromStr,
+{
+    match s {
+        "none" => Ok(None),
+        s => parse(s).map(Some),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Board;
+    use patterns;
+    use std::env;
+
+    #[test]
+    fn test_replay_round_trip() {
+        let mut a = VecTile::new(8);
+        {
+            let mut q = |x: usize, y: usize| a.set(x, y, true);
+            // Glider
+            q(0, 2);
+            q(1, 2);
+            q(2, 2);
+            q(2, 1);
+            q(1, 0);
+        }
+        let mut b = VecTile::new(8);
+        b.set(3, 3, true);
+        b.set(3, 4, true);
+        b.set(3, 5, true);
+
+        let (replay, board) = Replay::record(200, &a, &b, true, 3);
+        assert_eq!(replay.score, board.as_ref().map(|x| x.score()));
+        assert!(replay.verify().is_ok());
+        for engine in Engine::ALL.iter() {
+            assert_eq!(Replay::record_with(*engine, 200, &a, &b, true, 3).0, replay);
+        }
+
+        let path = env::temp_dir().join("life_struggle_test.replay");
+        replay.save(&path).unwrap();
+        let loaded = Replay::<VecTile>::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, replay);
+        assert!(loaded.verify().is_ok());
+
+        let mut wrong = loaded.clone();
+        wrong.board_hash ^= 1;
+        let divergence = wrong.verify().unwrap_err();
+        assert_eq!(divergence.hash, replay.board_hash);
+    }
+
+    #[test]
+    fn test_replay_policy() {
+        let mut block = VecTile::new(8);
+        block.set(3, 3, true);
+        block.set(4, 3, true);
+        block.set(3, 4, true);
+        block.set(4, 4, true);
+        let empty = VecTile::new(8);
+
+        let policy = TerminationPolicy::decided(8);
+        let (replay, outcome) =
+            Replay::record_until(Engine::ByteTile, 1000, &block, &empty, false, 0, policy);
+        assert_eq!(replay.reason, StopReason::Stable);
+        assert!(replay.stopped_at.unwrap() < 10);
+        assert_eq!(replay.stopped_at, Some(outcome.generation));
+        assert_eq!(replay.score, Some(outcome.score));
+        assert!(replay.verify().is_ok());
+
+        let path = env::temp_dir().join(format!(
+            "life_struggle_policy_{}.replay",
+            std::process::id()
+        ));
+        replay.save(&path).unwrap();
+        assert_eq!(Replay::<VecTile>::load(&path).unwrap(), replay);
+
+        // Capped below the widest a glider against loaves gets, it is adjudicated.
+        let roster = patterns::classic_roster::<VecTile>(8);
+        let find = |name: &str| &roster.iter().find(|(n, _)| n == name).unwrap().1;
+        let (glider, loaf) = (find("Glider"), find("Loaf"));
+        let (full, _) =
+            Replay::record_until(Engine::ByteTile, 1000, glider, loaf, false, 0, policy);
+        let widest = full.widest.unwrap();
+        assert!(widest > 1);
+        let policy = TerminationPolicy {
+            max_width: Some(widest - 1),
+            ..policy
+        };
+        let (replay, _) =
+            Replay::record_until(Engine::ByteTile, 1000, glider, loaf, false, 0, policy);
+        assert_eq!(replay.reason, StopReason::Adjudicated);
+        assert_eq!(replay.widest, full.widest);
+        assert!(replay.verify().is_ok());
+        replay.save(&path).unwrap();
+        assert_eq!(Replay::<VecTile>::load(&path).unwrap(), replay);
+
+        // Ending the same score another way, or at another generation or width, is a divergence.
+        let mut wrong = replay.clone();
+        wrong.reason = StopReason::Completed;
+        let divergence = wrong.verify().unwrap_err();
+        assert_eq!(divergence.reason, StopReason::Adjudicated);
+        assert!(divergence.to_string().contains("adjudicated at generation"));
+        let mut wrong = replay.clone();
+        wrong.stopped_at = replay.stopped_at.map(|g| g + 1);
+        assert!(wrong.verify().is_err());
+        let mut wrong = replay.clone();
+        wrong.widest = Some(widest + 1);
+        assert!(wrong.verify().is_err());
+
+        // Replays from before policies played every generation, and didn't record
+        // where or how wide they stopped. A lone cell against nothing converges at once.
+        let mut dot = VecTile::new(8);
+        dot.set(3, 3, true);
+        for (a, reason) in [
+            (&block, StopReason::Completed),
+            (&dot, StopReason::ConvergenceDraw),
+        ]
+        .iter()
+        {
+            let (mut full, _) = Replay::record(1000, *a, &empty, false, 0);
+            full.save(&path).unwrap();
+            let text = fs::read_to_string(&path).unwrap();
+            let old: String = text
+                .lines()
+                .take_while(|l| !l.starts_with("stable"))
+                .map(|l| format!("{}\n", l))
+                .collect();
+            fs::write(&path, old).unwrap();
+            let loaded = Replay::<VecTile>::load(&path).unwrap();
+            assert_eq!(loaded.reason, *reason);
+            assert!(loaded.verify().is_ok());
+            full.stopped_at = None;
+            full.widest = None;
+            assert_eq!(loaded, full);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_files() {
+        let path = env::temp_dir().join(format!("life_struggle_bad_{}.replay", std::process::id()));
+        let (replay, _) = Replay::record(10, &VecTile::new(8), &VecTile::new(8), false, 0);
+        replay.save(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let a = tile_to_hex(&replay.tile_a);
+
+        let bad = [
+            text.replace(RULE, "B36/S23"),
+            // Too short and too long for the size.
+            text.replacen(&a, &a[2..], 1),
+            text.replacen(&a, &format!("{}00", a), 1),
+            text.replace("size 8", "size 9"),
+        ];
+        for b in bad.iter() {
+            fs::write(&path, b).unwrap();
+            let e = Replay::<VecTile>::load(&path).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,387 @@
+//! HTML and Markdown tournament reports.
+
+use board::save_image;
+use player::Player;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use termination::{Outcome, StopReason};
+use tile::*;
+use tournament::Standing;
+
+/// One match played in a tournament, by player index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Index of player a.
+    pub a: usize,
+    /// Index of player b.
+    pub b: usize,
+    /// a's score first, None for a convergence draw.
+    pub score: Option<(isize, isize)>,
+    /// Why the match stopped, for example adjudicated for being too wide.
+    pub reason: StopReason,
+    /// Generation the match stopped at.
+    pub generation: usize,
+    /// Widest the contested region got, in tiles.
+    pub widest: usize,
+    /// Whether the board was saved to match_image_path before the match was added.
+    pub image: bool,
+}
+
+/// Static pages describing one tournament, for browsing results offline:
+/// standings, a cross-table of scores, every match and how it ended, and thumbnails of every tile,
+/// linking to images of the matches, written as index.html and index.md in a directory.
+///
+/// Layout of the directory:
+/// index.html, index.md, tiles/<player>.png and matches/<match>-<a>-<b>.png (see match_image_path),
+/// numbered as formats such as Swiss can pair the same players more than once.
+pub struct Report<'a, T>
+where
+    T: LifeTile + 'a,
+{
+    dir: PathBuf,
+    title: String,
+    players: &'a [Player<T>],
+    matches: Vec<Match>,
+}
+
+impl<'a, T> Report<'a, T>
+where
+    T: LifeTile,
+{
+    /// A report on a tournament between players, indexed as in the tournament,
+    /// creating dir and its subdirectories.
+    pub fn new<Q>(dir: Q, title: &str, players: &'a [Player<T>]) -> io::Result<Report<'a, T>>
+    where
+        Q: AsRef<Path>,
+    {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join("tiles"))?;
+        fs::create_dir_all(dir.join("matches"))?;
+        Ok(Report {
+            dir,
+            title: title.to_string(),
+            players,
+            matches: vec![],
+        })
+    }
+
+    /// Where to save the image of the next match added, between a and b
+    /// (for example with Board::print_image).
+    pub fn match_image_path(&self, a: usize, b: usize) -> PathBuf {
+        self.dir.join(match_image(self.matches.len(), a, b))
+    }
+
+    /// Add a match between a and b which ended with outcome.
+    pub fn add_match<B>(&mut self, a: usize, b: usize, outcome: &Outcome<B>, image: bool) {
+        self.matches.push(Match {
+            a,
+            b,
+            score: outcome.board.as_ref().map(|_| outcome.score),
+            reason: outcome.reason,
+            generation: outcome.generation,
+            widest: outcome.max_width,
+            image,
+        });
+    }
+
+    /// Write thumbnails, index.html and index.md, for the tournament's final standings.
+    pub fn write(&self, standings: &[Standing]) -> io::Result<()> {
+        for (i, p) in self.players.iter().enumerate() {
+            save_image(&[&p.tile], self.dir.join(thumbnail(i)));
+        }
+        fs::write(self.dir.join("index.html"), self.html(standings))?;
+        fs::write(self.dir.join("index.md"), self.markdown(standings))
+    }
+
+    /// The HTML page.
+    pub fn html(&self, standings: &[Standing]) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>\n\
+             table {{ border-collapse: collapse; }}\n\
+             td, th {{ border: 1px solid #ccc; padding: 2px 6px; text-align: right; }}\n\
+             img {{ image-rendering: pixelated; height: 48px; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape_html(&self.title)
+        );
+
+        html.push_str(
+            "<h2>Standings</h2>\n<table>\n<tr><th>#</th><th>Player</th><th>Tile</th>\
+             <th>Points</th><th>Won</th><th>Lost</th><th>Drawn</th><th>Byes</th>\
+             <th>Point difference</th><th>Buchholz</th><th>Stage</th></tr>\n",
+        );
+        for (rank, s) in standings.iter().enumerate() {
+            let r = &s.record;
+            html.push_str(&format!(
+                "<tr><td>{}</td><th>{}</th><td><img src=\"{}\"></td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                rank + 1,
+                escape_html(&self.players[s.player].name),
+                thumbnail(s.player),
+                s.points,
+                r.wins,
+                r.losses,
+                r.draws,
+                s.byes,
+                r.point_difference,
+                s.buchholz,
+                s.stage
+            ));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Cross-table</h2>\n<p>Row player's score first; = is a convergence draw.</p>\n<table>\n<tr><th></th>");
+        for s in standings {
+            html.push_str(&format!(
+                "<th>{}</th>",
+                escape_html(&self.players[s.player].name)
+            ));
+        }
+        html.push_str("</tr>\n");
+        for row in standings {
+            html.push_str(&format!(
+                "<tr><th>{}</th>",
+                escape_html(&self.players[row.player].name)
+            ));
+            for column in standings {
+                let cell = match self.find(row.player, column.player) {
+                    Some((text, Some(image))) => format!("<a href=\"{}\">{}</a>", image, text),
+                    Some((text, None)) => text,
+                    None => String::new(),
+                };
+                html.push_str(&format!("<td>{}</td>", cell));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+
+        html.push_str(
+            "<h2>Matches</h2>\n<table>\n<tr><th>#</th><th>Player</th><th>Opponent</th>\
+             <th>Score</th><th>Ended</th><th>Generation</th><th>Widest</th></tr>\n",
+        );
+        for (index, m) in self.matches.iter().enumerate() {
+            let score = match self.score_text(index, m.a) {
+                (text, Some(image)) => format!("<a href=\"{}\">{}</a>", image, text),
+                (text, None) => text,
+            };
+            html.push_str(&format!(
+                "<tr><td>{}</td><th>{}</th><th>{}</th><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                index + 1,
+                escape_html(&self.players[m.a].name),
+                escape_html(&self.players[m.b].name),
+                score,
+                m.reason,
+                m.generation,
+                m.widest
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// The Markdown page.
+    pub fn markdown(&self, standings: &[Standing]) -> String {
+        let mut md = format!("# {}\n\n## Standings\n\n", escape_markdown(&self.title));
+        md.push_str(
+            "| # | Player | Tile | Points | Won | Lost | Drawn | Byes | Point difference | Buchholz | Stage |\n\
+             |--:|---|---|--:|--:|--:|--:|--:|--:|--:|--:|\n",
+        );
+        for (rank, s) in standings.iter().enumerate() {
+            let r = &s.record;
+            md.push_str(&format!(
+                "| {} | {} | ![]({}) | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                rank + 1,
+                escape_markdown(&self.players[s.player].name),
+                thumbnail(s.player),
+                s.points,
+                r.wins,
+                r.losses,
+                r.draws,
+                s.byes,
+                r.point_difference,
+                s.buchholz,
+                s.stage
+            ));
+        }
+
+        md.push_str(
+            "\n## Cross-table\n\nRow player's score first; = is a convergence draw.\n\n|   |",
+        );
+        for s in standings {
+            md.push_str(&format!(
+                " {} |",
+                escape_markdown(&self.players[s.player].name)
+            ));
+        }
+        md.push_str("\n|---|");
+        for _ in standings {
+            md.push_str("--:|");
+        }
+        md.push('\n');
+        for row in standings {
+            md.push_str(&format!(
+                "| {} |",
+                escape_markdown(&self.players[row.player].name)
+            ));
+            for column in standings {
+                let cell = match self.find(row.player, column.player) {
+                    Some((text, Some(image))) => format!("[{}]({})", text, image),
+                    Some((text, None)) => text,
+                    None => String::new(),
+                };
+                md.push_str(&format!(" {} |", cell));
+            }
+            md.push('\n');
+        }
+
+        md.push_str(
+            "\n## Matches\n\n| # | Player | Opponent | Score | Ended | Generation | Widest |\n\
+             |--:|---|---|--:|---|--:|--:|\n",
+        );
+        for (index, m) in self.matches.iter().enumerate() {
+            let score = match self.score_text(index, m.a) {
+                (text, Some(image)) => format!("[{}]({})", text, image),
+                (text, None) => text,
+            };
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} |\n",
+                index + 1,
+                escape_markdown(&self.players[m.a].name),
+                escape_markdown(&self.players[m.b].name),
+                score,
+                m.reason,
+                m.generation,
+                m.widest
+            ));
+        }
+        md
+    }
+
+    // Score of the last match between a and b from a's point of view, and the link to its image.
+    fn find(&self, a: usize, b: usize) -> Option<(String, Option<String>)> {
+        let (index, _) = self
+            .matches
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, m)| (m.a, m.b) == (a, b) || (m.a, m.b) == (b, a))?;
+        Some(self.score_text(index, a))
+    }
+
+    // Score of the match at index from player's point of view, and the link to its image.
+    fn score_text(&self, index: usize, player: usize) -> (String, Option<String>) {
+        let m = &self.matches[index];
+        let text = match m.score {
+            Some((sa, sb)) if m.a == player => format!("{}&ndash;{}", sa, sb),
+            Some((sa, sb)) => format!("{}&ndash;{}", sb, sa),
+            None => "=".to_string(),
+        };
+        let image = if m.image {
+            Some(match_image(index, m.a, m.b))
+        } else {
+            None
+        };
+        (text, image)
+    }
+}
+
+// Paths relative to the report's directory.
+fn thumbnail(player: usize) -> String {
+    format!("tiles/{}.png", player)
+}
+
+fn match_image(index: usize, a: usize, b: usize) -> String {
+    format!("matches/{:03}-{}-{}.png", index, a, b)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if "\\`*_[]()#|<>".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tournament::{RoundRobin, Tournament};
+
+    // A match which ran for 1000 generations with this score, None for a convergence draw.
+    fn completed(score: Option<(isize, isize)>) -> Outcome<()> {
+        Outcome {
+            board: score.map(|_| ()),
+            generation: 1000,
+            reason: score.map_or(StopReason::ConvergenceDraw, |_| StopReason::Completed),
+            score: score.unwrap_or((0, 0)),
+            max_width: 3,
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let players: Vec<Player<VecTile>> = ["Block", "<Glider>", "Lwss|2"]
+            .iter()
+            .map(|name| Player::new(VecTile::new(8), name.to_string(), false))
+            .collect();
+        let dir = env::temp_dir().join(format!("life_struggle_report_{}", std::process::id()));
+        let mut report = Report::new(&dir, "Round 1 <swiss_3>", &players).unwrap();
+        let mut play = |a: usize, b: usize| {
+            let score = if a == 0 && b == 2 { None } else { Some((3, 1)) };
+            report.add_match(a, b, &completed(score), b == 1);
+            score
+        };
+        let standings = RoundRobin.run(players.len(), &mut play);
+        // A rematch gets its own image, and the cross-table shows the latest result.
+        assert!(report
+            .match_image_path(2, 1)
+            .ends_with("matches/003-2-1.png"));
+        let adjudicated = Outcome {
+            board: Some(()),
+            generation: 400,
+            reason: StopReason::Adjudicated,
+            score: (5, 0),
+            max_width: 65,
+        };
+        report.add_match(2, 1, &adjudicated, true);
+        report.write(&standings).unwrap();
+
+        let html = fs::read_to_string(dir.join("index.html")).unwrap();
+        assert!(html.contains("&lt;Glider&gt;"));
+        assert!(!html.contains("<Glider>"));
+        // Player 0 beat player 1 3-1, with an image; player 1 lost to 0 from its row.
+        assert!(html.contains("<a href=\"matches/000-0-1.png\">3&ndash;1</a>"));
+        assert!(html.contains("<a href=\"matches/000-0-1.png\">1&ndash;3</a>"));
+        assert!(html.contains("<td>=</td>"));
+        assert!(html.contains("<a href=\"matches/003-2-1.png\">0&ndash;5</a>"));
+        assert!(html.contains("<td>adjudicated</td><td>400</td><td>65</td>"));
+
+        let md = fs::read_to_string(dir.join("index.md")).unwrap();
+        assert!(md.starts_with("# Round 1 \\<swiss\\_3\\>\n"));
+        assert!(md.contains("Lwss\\|2"));
+        assert!(md.contains("[3&ndash;1](matches/000-0-1.png)"));
+        assert!(md.contains(
+            "| 4 | Lwss\\|2 | \\<Glider\\> | [5&ndash;0](matches/003-2-1.png) | adjudicated | 400 | 65 |"
+        ));
+        assert!(md.contains("| 2 | Block | Lwss\\|2 | = | convergence-draw | 1000 | 3 |"));
+        for i in 0..3 {
+            assert!(dir.join(thumbnail(i)).exists());
+        }
+        // Header, separator and a row per player, in both tables, then a row per match.
+        let rows = md.lines().filter(|l| l.starts_with('|')).count();
+        assert_eq!(rows, 2 * (2 + players.len()) + 2 + 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,479 @@
+//! Ways of scoring a board.
+
+use board::Board;
+use std::collections::VecDeque;
+use tile::*;
+
+/// Scoring rules for a Board.
+/// Each scorer returns (score_a, score_b), with positive values meaning a player did well.
+/// Like the board itself, scores are relative to the x=0 split:
+/// tiles at x < 0 are player a's territory, and tiles at x >= 0 are player b's.
+pub trait Scorer {
+    /// (score_a, score_b) for board as it is now.
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>;
+}
+
+/// The rule from the README:
+/// 1 point added for each tile of enemy territory converted into your tile,
+/// 1 point deducted for each tile of your territory disrupted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaptureScorer;
+
+impl Scorer for CaptureScorer {
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        let a = board.a_current();
+        let b = board.b_current();
+        territory_score(board, 1, |t| ((t == a) as isize, (t == b) as isize))
+    }
+}
+
+/// Scores how far the contested region has moved from x=0,
+/// ignoring what the contested tiles look like.
+/// Measured in half tiles, since the frontier is the middle of the contested region.
+/// Always zero sum.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrontierScorer;
+
+impl Scorer for FrontierScorer {
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        // The first and last tiles of the contested region move away from -1 and 0
+        // as a advances, so their sum is twice the frontier position.
+        let s = board.lowest_non_a() + board.highest_non_b() + 1;
+        (s, -s)
+    }
+}
+
+/// Like CaptureScorer, but scored per cell instead of per tile:
+/// each cell that matches a player's tile counts as partially converting that tile.
+/// Cells where a and b's tiles agree can't tell the players apart, so only
+/// the cells where they differ count, and a whole tile is worth that many points.
+/// Always zero sum.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HammingScorer;
+
+impl Scorer for HammingScorer {
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        let a = board.a_current();
+        let b = board.b_current();
+        let size = board.tile_size();
+        let full = (size * size - matching_cells(a, b)) as isize;
+        territory_score(board, full, |t| {
+            let match_a = matching_cells(t, a) as isize;
+            let match_b = matching_cells(t, b) as isize;
+            // Every differing cell matches exactly one of a or b.
+            // Cells matching both are the ones where a and b agree.
+            let both = (match_a + match_b - full) / 2;
+            (match_a - both, match_b - both)
+        })
+    }
+}
+
+/// Like CaptureScorer, but a tile is owned by whichever player's tile
+/// it matches more cells of, instead of requiring an exact match.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MajorityScorer;
+
+impl Scorer for MajorityScorer {
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        let a = board.a_current();
+        let b = board.b_current();
+        territory_score(board, 1, |t| {
+            let match_a = matching_cells(t, a);
+            let match_b = matching_cells(t, b);
+            ((match_a > match_b) as isize, (match_b > match_a) as isize)
+        })
+    }
+}
+
+/// Like CaptureScorer, but a tile also counts as a player's if it matches
+/// any phase of that player's self-evolution cycle (when `phases` is set)
+/// and/or any shift of it along y (when `y_shifts` is set).
+/// This way a captured tile that is out of phase with the background still counts.
+/// Exact matches take priority; a tile tolerantly matching both players counts for neither.
+#[derive(Debug, Clone, Copy)]
+pub struct TolerantScorer {
+    /// Match any phase of a player's cycle?
+    pub phases: bool,
+    /// Match any shift of a player's tile along y?
+    pub y_shifts: bool,
+    /// Longest self-evolution cycle to search for.
+    /// Tiles which don't repeat within this many generations are only matched in their current phase.
+    pub max_period: usize,
+}
+
+impl Default for TolerantScorer {
+    fn default() -> TolerantScorer {
+        TolerantScorer {
+            phases: true,
+            y_shifts: true,
+            max_period: 64,
+        }
+    }
+}
+
+/// Scores of the same board with and without tolerant matching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchScores {
+    /// CaptureScorer's score.
+    pub strict: (isize, isize),
+    /// TolerantScorer's score.
+    pub tolerant: (isize, isize),
+}
+
+impl TolerantScorer {
+    /// Score board both strictly and tolerantly, to see how much tolerance changes.
+    pub fn score_both<T, B>(&self, board: &B) -> MatchScores
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        MatchScores {
+            strict: CaptureScorer.score(board),
+            tolerant: self.score(board),
+        }
+    }
+
+    /// All tiles which count as matching t.
+    fn variants<T>(&self, t: &T) -> Vec<T>
+    where
+        T: LifeTile,
+    {
+        let phases = if self.phases {
+            self_cycle(t, self.max_period)
+        } else {
+            vec![t.clone()]
+        };
+
+        if !self.y_shifts {
+            return phases;
+        }
+
+        let mut variants = Vec::with_capacity(phases.len() * t.size());
+        for p in &phases {
+            for dy in 0..t.size() {
+                let v = p.shift_y(dy);
+                if !variants.contains(&v) {
+                    variants.push(v);
+                }
+            }
+        }
+        variants
+    }
+}
+
+impl Scorer for TolerantScorer {
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        let a = board.a_current();
+        let b = board.b_current();
+        let a_variants = self.variants(a);
+        let b_variants = self.variants(b);
+        territory_score(board, 1, |t| {
+            if t == a {
+                (1, 0)
+            } else if t == b {
+                (0, 1)
+            } else {
+                match (a_variants.contains(t), b_variants.contains(t)) {
+                    (true, false) => (1, 0),
+                    (false, true) => (0, 1),
+                    _ => (0, 0),
+                }
+            }
+        })
+    }
+}
+
+/// The phases of t's self-evolution cycle (t tiled with itself), starting with t.
+/// If t does not return to itself within max_period generations, just t.
+fn self_cycle<T>(t: &T, max_period: usize) -> Vec<T>
+where
+    T: LifeTile,
+{
+    let mut phases = vec![t.clone()];
+    for _ in 0..max_period {
+        let next = {
+            let last = phases.last().unwrap();
+            last.next_generation(last, last)
+        };
+        if &next == t {
+            return phases;
+        }
+        phases.push(next);
+    }
+    phases.truncate(1);
+    phases
+}
+
+/// Selects one of the built in scorers at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreMode {
+    /// CaptureScorer.
+    #[default]
+    Capture,
+    /// FrontierScorer.
+    Frontier,
+    /// HammingScorer.
+    Hamming,
+    /// MajorityScorer.
+    Majority,
+    /// TolerantScorer with its default settings
+    Tolerant,
+}
+
+impl Scorer for ScoreMode {
+    fn score<T, B>(&self, board: &B) -> (isize, isize)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        match *self {
+            ScoreMode::Capture => CaptureScorer.score(board),
+            ScoreMode::Frontier => FrontierScorer.score(board),
+            ScoreMode::Hamming => HammingScorer.score(board),
+            ScoreMode::Majority => MajorityScorer.score(board),
+            ScoreMode::Tolerant => TolerantScorer::default().score(board),
+        }
+    }
+}
+
+/// Averages another scorer over the last `window` generations observed,
+/// so a single lucky (or unlucky) final generation matters less.
+/// Call observe once per generation.
+#[derive(Debug, Clone)]
+pub struct TimeAveragedScorer<S>
+where
+    S: Scorer,
+{
+    inner: S,
+    window: usize,
+    history: VecDeque<(isize, isize)>,
+}
+
+impl<S> TimeAveragedScorer<S>
+where
+    S: Scorer,
+{
+    /// Average inner's scores over the last window generations.
+    pub fn new(inner: S, window: usize) -> TimeAveragedScorer<S> {
+        assert!(window > 0);
+        TimeAveragedScorer {
+            inner,
+            window,
+            history: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Score board as the latest generation.
+    pub fn observe<T, B>(&mut self, board: &B)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.inner.score(board));
+    }
+
+    /// Number of generations currently included in the average.
+    pub fn observed(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns (0, 0) if nothing has been observed.
+    pub fn average(&self) -> (f64, f64) {
+        if self.history.is_empty() {
+            return (0.0, 0.0);
+        }
+
+        let (sum_a, sum_b) = self
+            .history
+            .iter()
+            .fold((0, 0), |(sa, sb), &(a, b)| (sa + a, sb + b));
+        let n = self.history.len() as f64;
+        (sum_a as f64 / n, sum_b as f64 / n)
+    }
+}
+
+/// Number of cells in which t and other agree.
+fn matching_cells<T>(t: &T, other: &T) -> usize
+where
+    T: LifeTile,
+{
+    let size = t.size();
+    let mut c = 0;
+    for y in 0..size {
+        for x in 0..size {
+            if t.get(x, y) == other.get(x, y) {
+                c += 1;
+            }
+        }
+    }
+    c
+}
+
+/// Shared logic for scorers that assign each tile a degree of ownership.
+/// `ownership` returns how much of a tile belongs to (a, b), each between 0 and `full`.
+/// For each tile, the owner of the territory loses (full - their ownership)
+/// and the other player gains their ownership.
+/// Tiles outside the contested region exactly match a or b, so are worth `full`.
+fn territory_score<T, B, F>(board: &B, full: isize, ownership: F) -> (isize, isize)
+where
+    T: LifeTile,
+    B: Board<T>,
+    F: Fn(&T) -> (isize, isize),
+{
+    let first = board.lowest_non_a();
+    let last = board.highest_non_b();
+
+    // Uncontested tiles on the wrong side of x=0
+    let a_captured = first.max(0);
+    let b_captured = (-last - 1).max(0);
+    let mut score_a = full * (a_captured - b_captured);
+    let mut score_b = -score_a;
+
+    for x in first..=last {
+        let (own_a, own_b) = ownership(board.tile_at(x));
+        if x < 0 {
+            score_a -= full - own_a;
+            score_b += own_b;
+        } else {
+            score_a += own_a;
+            score_b -= full - own_b;
+        }
+    }
+
+    (score_a, score_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::VecBoard;
+    use game::struggle_board;
+    use snapshot::Snapshot;
+
+    fn lwss() -> VecTile {
+        let mut t = VecTile::new(8);
+        {
+            let mut q = |x: usize, y: usize| t.set(x, y, true);
+            q(0, 0);
+            q(0, 2);
+            q(1, 3);
+            q(2, 3);
+            q(3, 3);
+            q(4, 3);
+            q(4, 2);
+            q(4, 1);
+            q(3, 0);
+        }
+        t
+    }
+
+    #[test]
+    fn test_scorers_lwss_vs_empty() {
+        let a = lwss();
+        let b = VecTile::new(8);
+        let board = struggle_board(100, &a, &b).unwrap();
+
+        assert_eq!(CaptureScorer.score(&board), (6, -6));
+        assert_eq!(ScoreMode::Capture.score(&board), board.score());
+
+        // Everything a didn't exactly capture is still mostly empty, like b.
+        let (frontier_a, frontier_b) = FrontierScorer.score(&board);
+        assert!(frontier_a > 0);
+        assert_eq!(frontier_a, -frontier_b);
+
+        let (hamming_a, hamming_b) = HammingScorer.score(&board);
+        assert!(hamming_a > 0);
+        assert_eq!(hamming_a, -hamming_b);
+
+        let (majority_a, majority_b) = MajorityScorer.score(&board);
+        assert!(majority_a >= 6);
+        assert!(majority_b <= -6);
+    }
+
+    #[test]
+    fn test_time_averaged() {
+        let a = lwss();
+        let b = VecTile::new(8);
+        let mut averaged = TimeAveragedScorer::new(CaptureScorer, 10);
+        assert_eq!(averaged.average(), (0.0, 0.0));
+
+        let board = ::game::struggle_board_with(100, &a, &b, |board| averaged.observe(board));
+        let board = board.unwrap();
+
+        assert_eq!(averaged.observed(), 10);
+        let (avg_a, avg_b) = averaged.average();
+        let (score_a, score_b) = board.score();
+        // Score only increases as the lwss advances
+        assert!(avg_a <= score_a as f64);
+        assert!(avg_b >= score_b as f64);
+        assert!(avg_a > 0.0);
+    }
+
+    #[test]
+    fn test_tolerant_out_of_phase() {
+        let a = lwss();
+        let b = VecTile::new(8);
+
+        // The lwss moves 2 cells every 4 generations, so wraps around the 8 wide tile in 16.
+        assert_eq!(self_cycle(&a, 64).len(), 16);
+
+        // Captured tiles in b's territory: one a phase ahead of the background, one shifted along y.
+        let (board, _) = VecBoard::from_snapshot(Snapshot {
+            generation: 0,
+            vec_start: 0,
+            num_a_at_start: 0,
+            a: a.clone(),
+            b: b.clone(),
+            tiles: vec![a.next_generation(&a, &a), a.shift_y(3)],
+        });
+        assert_eq!(board.score(), (0, -2));
+
+        let strict = TolerantScorer {
+            phases: false,
+            y_shifts: false,
+            max_period: 64,
+        };
+        assert_eq!(strict.score(&board), board.score());
+        let phases = TolerantScorer {
+            y_shifts: false,
+            ..TolerantScorer::default()
+        };
+        assert_eq!(phases.score(&board), (1, -2));
+        let y_shifts = TolerantScorer {
+            phases: false,
+            ..TolerantScorer::default()
+        };
+        assert_eq!(y_shifts.score(&board), (1, -2));
+
+        let scores = TolerantScorer::default().score_both(&board);
+        assert_eq!(scores.strict, board.score());
+        assert!(scores.tolerant.0 > scores.strict.0);
+        assert_eq!(scores.tolerant, (2, -2));
+    }
+}
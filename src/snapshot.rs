@@ -0,0 +1,230 @@
+//! Saving and loading the exact state of a board.
+
+use board::Board;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use tile::*;
+
+const MAGIC: &[u8; 6] = b"LSSNAP";
+const FORMAT_VERSION: u8 = 1;
+/// Largest tile size read from a snapshot, so a corrupt header can't ask for a huge allocation.
+pub const MAX_TILE_SIZE: usize = 4096;
+
+/// The complete state of a 2 player board at some generation,
+/// enough to resume the struggle from that point.
+/// Matches the representation of VecBoard: `tiles` starts at x = vec_start,
+/// with num_a_at_start tiles which match a before the contested region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot<T>
+where
+    T: LifeTile,
+{
+    /// Generation the board was at.
+    pub generation: usize,
+    /// x of the first tile in tiles.
+    pub vec_start: isize,
+    /// Number of tiles at the start of tiles which match a, before the contested region.
+    pub num_a_at_start: isize,
+    /// Player a's tile evolved alone, the background to the -x.
+    pub a: T,
+    /// Player b's tile evolved alone, the background to the +x.
+    pub b: T,
+    /// Tiles from vec_start on, ending with the contested region.
+    pub tiles: Vec<T>,
+}
+
+impl<T> Snapshot<T>
+where
+    T: LifeTile,
+{
+    /// Snapshot any Board by copying its contested region.
+    /// For VecBoard, prefer VecBoard::snapshot, which preserves its exact layout.
+    pub fn from_board<B>(board: &B, generation: usize) -> Snapshot<T>
+    where
+        B: Board<T>,
+    {
+        let first = board.lowest_non_a();
+        let last = board.highest_non_b();
+        Snapshot {
+            generation,
+            vec_start: first,
+            num_a_at_start: 0,
+            a: board.a_current().clone(),
+            b: board.b_current().clone(),
+            tiles: (first..=last).map(|x| board.tile_at(x).clone()).collect(),
+        }
+    }
+
+    /// Compact binary format: a small header, then every tile as packed bits.
+    /// All integers are little endian.
+    pub fn write<W>(&self, w: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        w.write_all(MAGIC)?;
+        w.write_all(&[FORMAT_VERSION])?;
+        w.write_all(&(self.a.size() as u32).to_le_bytes())?;
+        w.write_all(&(self.generation as u64).to_le_bytes())?;
+        w.write_all(&(self.vec_start as i64).to_le_bytes())?;
+        w.write_all(&(self.num_a_at_start as i64).to_le_bytes())?;
+        w.write_all(&(self.tiles.len() as u64).to_le_bytes())?;
+        write_tile(w, &self.a)?;
+        write_tile(w, &self.b)?;
+        for t in &self.tiles {
+            write_tile(w, t)?;
+        }
+        Ok(())
+    }
+
+    /// Read the format written by write. Corrupt data is an InvalidData error.
+    pub fn read<R>(r: &mut R) -> io::Result<Snapshot<T>>
+    where
+        R: Read,
+    {
+        let mut magic = [0u8; 6];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a snapshot"));
+        }
+        let mut version = [0u8; 1];
+        r.read_exact(&mut version)?;
+        if version[0] != FORMAT_VERSION {
+            return Err(invalid_data("unsupported snapshot version"));
+        }
+
+        let size = read_u32(r)? as usize;
+        if size == 0 || size > MAX_TILE_SIZE {
+            return Err(invalid_data("tile size out of range"));
+        }
+        let generation = read_u64(r)? as usize;
+        let vec_start = read_u64(r)? as i64 as isize;
+        let num_a_at_start = read_u64(r)? as i64 as isize;
+        let count = read_u64(r)? as usize;
+        if num_a_at_start < 0 || num_a_at_start as usize > count {
+            return Err(invalid_data("num_a_at_start out of range"));
+        }
+
+        let a = read_tile(r, size).map_err(truncated)?;
+        let b = read_tile(r, size).map_err(truncated)?;
+        // Not reserved up front: a corrupt count runs out of tiles to read instead.
+        let mut tiles = vec![];
+        for _ in 0..count {
+            tiles.push(read_tile(r, size).map_err(truncated)?);
+        }
+
+        Ok(Snapshot {
+            generation,
+            vec_start,
+            num_a_at_start,
+            a,
+            b,
+            tiles,
+        })
+    }
+
+    /// Write to a temporary file and then move it into place,
+    /// so an interrupted save doesn't destroy the previous snapshot.
+    pub fn save<Q>(&self, path: Q) -> io::Result<()>
+    where
+        Q: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        {
+            let mut w = BufWriter::new(File::create(&tmp)?);
+            self.write(&mut w)?;
+            w.flush()?;
+        }
+        fs::rename(&tmp, path)
+    }
+
+    /// Read a file written by save.
+    pub fn load<Q>(path: Q) -> io::Result<Snapshot<T>>
+    where
+        Q: AsRef<Path>,
+    {
+        Snapshot::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// FNV-1a hash of the binary format, which is stable across platforms and Rust versions.
+    pub fn hash(&self) -> u64 {
+        let mut bytes = vec![];
+        self.write(&mut bytes).unwrap();
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for b in bytes {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+}
+
+/// Length of a size x size tile written by write_tile, None if too big to address.
+pub fn tile_bytes(size: usize) -> Option<usize> {
+    size.checked_mul(size).map(|cells| cells.div_ceil(8))
+}
+
+/// Cells in row major order, 8 per byte, least significant bit first.
+pub fn write_tile<W, T>(w: &mut W, t: &T) -> io::Result<()>
+where
+    W: Write,
+    T: LifeTileSrc,
+{
+    let size = t.size();
+    let mut bytes = vec![0u8; (size * size).div_ceil(8)];
+    for y in 0..size {
+        for x in 0..size {
+            if t.get(x, y) {
+                let i = x + y * size;
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+    }
+    w.write_all(&bytes)
+}
+
+/// Read a size x size tile written by write_tile.
+pub fn read_tile<R, T>(r: &mut R, size: usize) -> io::Result<T>
+where
+    R: Read,
+    T: LifeTile,
+{
+    let mut bytes = vec![0u8; (size * size).div_ceil(8)];
+    r.read_exact(&mut bytes)?;
+    let mut t = T::new(size);
+    for y in 0..size {
+        for x in 0..size {
+            let i = x + y * size;
+            if bytes[i / 8] & (1 << (i % 8)) != 0 {
+                t.set(x, y, true);
+            }
+        }
+    }
+    Ok(t)
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// The file ended before all the tiles its header promised.
+fn truncated(e: io::Error) -> io::Error {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        invalid_data("truncated snapshot")
+    } else {
+        e
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,303 @@
+//! Classifying how tiles behave when evolved alone, and filtering random tiles by it.
+
+use rand::Rng;
+use random::chance;
+use snapshot::write_tile;
+use std::collections::HashMap;
+use tile::*;
+
+/// What a tile eventually does evolved alone (tiled with itself, as the background of a match).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// Every cell dies.
+    Dies,
+    /// Stops changing.
+    StillLife,
+    /// Repeats every period generations, in place.
+    Oscillator {
+        /// Generations to repeat.
+        period: usize,
+    },
+    /// Repeats every period generations, moved by (dx, dy) cells.
+    /// Shifts are the smallest equivalent one, so dx and dy are in -size/2..=size/2.
+    Translating {
+        /// Generations to repeat.
+        period: usize,
+        /// Cells moved along x each period.
+        dx: isize,
+        /// Cells moved along y each period.
+        dy: isize,
+    },
+    /// Doesn't repeat within the generations analyzed.
+    Chaotic,
+}
+
+/// Result of analyze.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stability {
+    /// What the tile settles into.
+    pub class: Class,
+    /// Generation the tile entered its cycle, None if Chaotic.
+    pub settled_at: Option<usize>,
+    /// Fraction of the tile's cells which are alive, before any evolution.
+    pub density: f64,
+}
+
+/// Evolve tile alone for up to generations, and classify what it settles into.
+pub fn analyze<T>(tile: &T, generations: usize) -> Stability
+where
+    T: LifeTile,
+{
+    let size = tile.size();
+    let density = population(tile) as f64 / (size * size) as f64;
+
+    // Generation each state was first seen, to find the cycle.
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut history = vec![];
+    let mut t = tile.clone();
+    for generation in 0..=generations {
+        let mut key = vec![];
+        write_tile(&mut key, &t).unwrap();
+        if let Some(&start) = seen.get(&key) {
+            return Stability {
+                class: classify_cycle(&history[start..]),
+                settled_at: Some(start),
+                density,
+            };
+        }
+        seen.insert(key, generation);
+        history.push(t.clone());
+        t = t.next_generation(&t, &t);
+    }
+    Stability {
+        class: Class::Chaotic,
+        settled_at: None,
+        density,
+    }
+}
+
+/// Just the class from analyze.
+pub fn classify<T>(tile: &T, generations: usize) -> Class
+where
+    T: LifeTile,
+{
+    analyze(tile, generations).class
+}
+
+// Class of a cycle of states, which repeats exactly.
+fn classify_cycle<T>(cycle: &[T]) -> Class
+where
+    T: LifeTile,
+{
+    let first = &cycle[0];
+    if cycle.len() == 1 {
+        return if population(first) == 0 {
+            Class::Dies
+        } else {
+            Class::StillLife
+        };
+    }
+
+    // A moving pattern on a torus comes back to where it started eventually,
+    // but reappears shifted sooner.
+    let size = first.size() as isize;
+    let smallest = |d: usize| {
+        let d = d as isize;
+        if d > size / 2 {
+            d - size
+        } else {
+            d
+        }
+    };
+    for (period, state) in cycle.iter().enumerate().skip(1) {
+        for dy in 0..first.size() {
+            for dx in 0..first.size() {
+                if (dx, dy) != (0, 0) && shifted_equal(first, state, dx, dy) {
+                    return Class::Translating {
+                        period,
+                        dx: smallest(dx),
+                        dy: smallest(dy),
+                    };
+                }
+            }
+        }
+    }
+    Class::Oscillator {
+        period: cycle.len(),
+    }
+}
+
+// Is b a moved by (dx, dy), wrapping around?
+fn shifted_equal<T>(a: &T, b: &T, dx: usize, dy: usize) -> bool
+where
+    T: LifeTile,
+{
+    let size = a.size();
+    (0..size).all(|y| (0..size).all(|x| a.get(x, y) == b.get((x + dx) % size, (y + dy) % size)))
+}
+
+fn population<T>(t: &T) -> usize
+where
+    T: LifeTile,
+{
+    let size = t.size();
+    (0..size)
+        .map(|y| (0..size).filter(|&x| t.get(x, y)).count())
+        .sum()
+}
+
+/// Chance of keeping a tile of each class, for Filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    /// Chance of keeping Class::Dies.
+    pub dies: f64,
+    /// Chance of keeping Class::StillLife.
+    pub still_life: f64,
+    /// Chance of keeping Class::Oscillator.
+    pub oscillator: f64,
+    /// Chance of keeping Class::Translating.
+    pub translating: f64,
+    /// Chance of keeping Class::Chaotic.
+    pub chaotic: f64,
+}
+
+impl Weights {
+    /// Chance of keeping a tile of class.
+    pub fn of(&self, class: Class) -> f64 {
+        match class {
+            Class::Dies => self.dies,
+            Class::StillLife => self.still_life,
+            Class::Oscillator { .. } => self.oscillator,
+            Class::Translating { .. } => self.translating,
+            Class::Chaotic => self.chaotic,
+        }
+    }
+}
+
+/// Rejects tiles which would waste matches, such as ones which die out on their own,
+/// by density and stability class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Filter {
+    /// Generations to analyze tiles for (see analyze).
+    pub generations: usize,
+    /// Tiles with a density outside min_density..=max_density are always rejected.
+    pub min_density: f64,
+    /// See min_density.
+    pub max_density: f64,
+    /// Chance of keeping tiles of each class.
+    pub weights: Weights,
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter {
+            generations: 200,
+            min_density: 0.05,
+            max_density: 0.6,
+            weights: Weights {
+                dies: 0.0,
+                still_life: 0.25,
+                oscillator: 0.5,
+                translating: 1.0,
+                chaotic: 0.5,
+            },
+        }
+    }
+}
+
+impl Filter {
+    /// Keep a tile with this stability?
+    /// Randomly, with probability given by its class's weight.
+    pub fn accept<R: Rng>(&self, stability: &Stability, rng: &mut R) -> bool {
+        if stability.density < self.min_density || stability.density > self.max_density {
+            return false;
+        }
+        chance(rng, self.weights.of(stability.class))
+    }
+
+    /// Analyze and accept tile.
+    pub fn accept_tile<T, R>(&self, tile: &T, rng: &mut R) -> bool
+    where
+        T: LifeTile,
+        R: Rng,
+    {
+        self.accept(&analyze(tile, self.generations), rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use patterns;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn pattern(name: &str) -> VecTile {
+        let mut t = VecTile::new(16);
+        patterns::find(name)
+            .unwrap()
+            .stamp(&mut t, 4, 4, patterns::Orientation::IDENTITY);
+        t
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&VecTile::new(8), 10), Class::Dies);
+        let mut t = VecTile::new(8);
+        t.set(3, 3, true);
+        let s = analyze(&t, 10);
+        assert_eq!(s.class, Class::Dies);
+        assert_eq!(s.settled_at, Some(1));
+        assert_eq!(s.density, 1.0 / 64.0);
+
+        assert_eq!(classify(&pattern("Block"), 10), Class::StillLife);
+        assert_eq!(
+            classify(&pattern("Blinker"), 10),
+            Class::Oscillator { period: 2 }
+        );
+        assert_eq!(
+            classify(&pattern("Pulsar"), 10),
+            Class::Oscillator { period: 3 }
+        );
+        assert_eq!(
+            classify(&pattern("Glider"), 100),
+            Class::Translating {
+                period: 4,
+                dx: 1,
+                dy: 1
+            }
+        );
+        match classify(&pattern("Lwss"), 100) {
+            Class::Translating {
+                period: 4,
+                dx,
+                dy: 0,
+            } => assert_eq!(dx.abs(), 2),
+            c => panic!("{:?}", c),
+        }
+        // Not enough generations to find the glider's cycle.
+        assert_eq!(classify(&pattern("Glider"), 10), Class::Chaotic);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut rng = StdRng::from_seed([3; 32]);
+        let filter = Filter::default();
+        assert!(!filter.accept_tile(&VecTile::new(8), &mut rng));
+        // Too sparse on a 16x16 tile, but not on an 8x8 one.
+        assert!(!filter.accept_tile(&pattern("Glider"), &mut rng));
+        let mut glider = VecTile::new(8);
+        patterns::find("Glider")
+            .unwrap()
+            .stamp(&mut glider, 1, 1, patterns::Orientation::IDENTITY);
+        assert!(filter.accept_tile(&glider, &mut rng));
+
+        let mut full = VecTile::new(8);
+        for y in 0..8 {
+            for x in 0..8 {
+                full.set(x, y, true);
+            }
+        }
+        assert!(!filter.accept_tile(&full, &mut rng));
+    }
+}
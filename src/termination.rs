@@ -0,0 +1,284 @@
+//! Stopping struggles early once their outcome is decided.
+
+use board::Board;
+use snapshot::Snapshot;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use tile::*;
+
+/// When to stop a struggle before the full number of generations,
+/// once its outcome is decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TerminationPolicy {
+    /// Stop when the whole board, including the backgrounds, is unchanged from the previous generation.
+    pub stable: bool,
+    /// Stop when either player's score exceeds this.
+    pub capture_threshold: Option<isize>,
+    /// Stop when the board repeats a state from up to this many generations ago.
+    /// 0 disables cycle detection.
+    pub cycle_window: usize,
+    /// Stop when the contested region grows wider than this many tiles,
+    /// so a tile spraying endless debris can't make a struggle arbitrarily slow.
+    pub max_width: Option<usize>,
+    /// How to decide the outcome when max_width is exceeded.
+    pub on_max_width: WidthOutcome,
+}
+
+/// How to decide a struggle stopped for being too wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WidthOutcome {
+    /// Use the score when stopped.
+    #[default]
+    Adjudicate,
+    /// Score (0, 0).
+    ChaoticDraw,
+}
+
+impl fmt::Display for WidthOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WidthOutcome::Adjudicate => write!(f, "adjudicate"),
+            WidthOutcome::ChaoticDraw => write!(f, "chaotic-draw"),
+        }
+    }
+}
+
+impl FromStr for WidthOutcome {
+    type Err = String;
+
+    /// Parses the names from Display.
+    fn from_str(s: &str) -> Result<WidthOutcome, String> {
+        match s {
+            "adjudicate" => Ok(WidthOutcome::Adjudicate),
+            "chaotic-draw" => Ok(WidthOutcome::ChaoticDraw),
+            _ => Err(format!("unknown width outcome: {}", s)),
+        }
+    }
+}
+
+impl TerminationPolicy {
+    /// Stop as soon as the outcome is decided, looking for cycles up to cycle_window long.
+    pub fn decided(cycle_window: usize) -> TerminationPolicy {
+        TerminationPolicy {
+            stable: true,
+            capture_threshold: None,
+            cycle_window,
+            max_width: None,
+            on_max_width: WidthOutcome::Adjudicate,
+        }
+    }
+}
+
+/// Why a struggle stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Ran for the full number of generations.
+    Completed,
+    /// Both players' tiles became the same.
+    ConvergenceDraw,
+    /// The board stopped changing.
+    Stable,
+    /// A player's score exceeded the capture threshold.
+    Captured,
+    /// The board repeats every `period` generations.
+    Cycle {
+        /// Generations to repeat.
+        period: usize,
+    },
+    /// The contested region exceeded max_width, and was scored as is.
+    Adjudicated,
+    /// The contested region exceeded max_width, and was declared a draw.
+    ChaoticDraw,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StopReason::Completed => write!(f, "completed"),
+            StopReason::ConvergenceDraw => write!(f, "convergence-draw"),
+            StopReason::Stable => write!(f, "stable"),
+            StopReason::Captured => write!(f, "captured"),
+            StopReason::Cycle { period } => write!(f, "cycle:{}", period),
+            StopReason::Adjudicated => write!(f, "adjudicated"),
+            StopReason::ChaoticDraw => write!(f, "chaotic-draw"),
+        }
+    }
+}
+
+impl FromStr for StopReason {
+    type Err = String;
+
+    /// Parses the names from Display.
+    fn from_str(s: &str) -> Result<StopReason, String> {
+        let mut parts = s.splitn(2, ':');
+        let reason = parts.next().unwrap_or("");
+        let period = parts.next().map(|p| {
+            p.parse::<usize>()
+                .map_err(|_| format!("bad period in stop reason: {}", s))
+        });
+        match (reason, period) {
+            ("completed", None) => Ok(StopReason::Completed),
+            ("convergence-draw", None) => Ok(StopReason::ConvergenceDraw),
+            ("stable", None) => Ok(StopReason::Stable),
+            ("captured", None) => Ok(StopReason::Captured),
+            ("cycle", Some(period)) => Ok(StopReason::Cycle { period: period? }),
+            ("adjudicated", None) => Ok(StopReason::Adjudicated),
+            ("chaotic-draw", None) => Ok(StopReason::ChaoticDraw),
+            _ => Err(format!("unknown stop reason: {}", s)),
+        }
+    }
+}
+
+/// The result of a struggle which may have stopped early.
+#[derive(Debug)]
+pub struct Outcome<B> {
+    /// None for a convergence draw.
+    pub board: Option<B>,
+    /// Generation the struggle stopped at.
+    pub generation: usize,
+    /// Why it stopped.
+    pub reason: StopReason,
+    /// Score the struggle would have had after the full number of generations.
+    /// For Stable and Cycle, this is exact. For Captured and Adjudicated, it is the score when stopped.
+    pub score: (isize, isize),
+    /// Widest the contested region got, in tiles.
+    pub max_width: usize,
+}
+
+impl<B> Outcome<B> {
+    /// The same outcome, with the board converted by f.
+    pub fn map<C, F>(self, f: F) -> Outcome<C>
+    where
+        F: FnOnce(B) -> C,
+    {
+        Outcome {
+            board: self.board.map(f),
+            generation: self.generation,
+            reason: self.reason,
+            score: self.score,
+            max_width: self.max_width,
+        }
+    }
+}
+
+/// Watches a board each generation, and decides when to stop according to a policy.
+pub struct Terminator {
+    policy: TerminationPolicy,
+    generations: usize,
+    // Recent generations, oldest first.
+    // The last entry is for the generation before the one being checked.
+    history: VecDeque<Seen>,
+    max_width: usize,
+}
+
+// What the Terminator remembers of one generation.
+struct Seen {
+    // Boards which differ in this can't be the same, so it is compared first.
+    summary: (isize, isize, (isize, isize)),
+    // Hash of the whole board, only computed if its summary matched an earlier generation's.
+    hash: Option<u64>,
+    score: (isize, isize),
+}
+
+impl Terminator {
+    /// generations is the full length of the struggle, used for projecting the final score.
+    pub fn new(policy: TerminationPolicy, generations: usize) -> Terminator {
+        Terminator {
+            policy,
+            generations,
+            history: VecDeque::new(),
+            max_width: 0,
+        }
+    }
+
+    /// Widest contested region seen so far, in tiles.
+    pub fn max_width(&self) -> usize {
+        self.max_width
+    }
+
+    /// Track the width of a board without checking whether to stop.
+    /// check does this itself, so this is only needed for the final board.
+    pub fn observe_width<T, B>(&mut self, board: &B)
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        self.max_width = self.max_width.max(board.contested_width());
+    }
+
+    /// Call with the board after each generation, in order.
+    /// Returns why to stop, and the projected final score, if the struggle should stop.
+    pub fn check<T, B>(
+        &mut self,
+        generation: usize,
+        board: &B,
+    ) -> Option<(StopReason, (isize, isize))>
+    where
+        T: LifeTile,
+        B: Board<T>,
+    {
+        self.observe_width(board);
+        let score = board.score();
+        if let Some(max_width) = self.policy.max_width {
+            if board.contested_width() > max_width {
+                return Some(match self.policy.on_max_width {
+                    WidthOutcome::Adjudicate => (StopReason::Adjudicated, score),
+                    WidthOutcome::ChaoticDraw => (StopReason::ChaoticDraw, (0, 0)),
+                });
+            }
+        }
+        if let Some(threshold) = self.policy.capture_threshold {
+            if score.0 > threshold || score.1 > threshold {
+                return Some((StopReason::Captured, score));
+            }
+        }
+
+        let window = if self.policy.cycle_window > 0 {
+            self.policy.cycle_window
+        } else if self.policy.stable {
+            1
+        } else {
+            return None;
+        };
+
+        // Hashing the whole board costs as much as a generation, so only do it when the
+        // frontiers and score repeat a recent generation's. A cycle is then found one period late,
+        // once both ends of a period have been hashed.
+        let summary = (board.lowest_non_a(), board.highest_non_b(), score);
+        let hash = if self.history.iter().any(|s| s.summary == summary) {
+            // Snapshot at generation 0, so identical boards hash the same regardless of generation.
+            Some(Snapshot::from_board(board, 0).hash())
+        } else {
+            None
+        };
+        let found = hash.and_then(|h| self.history.iter().rev().position(|s| s.hash == Some(h)));
+        if let Some(i) = found {
+            let period = i + 1;
+            if period == 1 && self.policy.stable {
+                return Some((StopReason::Stable, score));
+            }
+            if self.policy.cycle_window > 0 {
+                // The board at the end will match the one at the same point in the cycle.
+                let remaining = self.generations - generation;
+                let offset = remaining % period;
+                let projected = if offset == 0 {
+                    score
+                } else {
+                    self.history[self.history.len() - period + offset].score
+                };
+                return Some((StopReason::Cycle { period }, projected));
+            }
+        }
+
+        if self.history.len() == window {
+            self.history.pop_front();
+        }
+        self.history.push_back(Seen {
+            summary,
+            hash,
+            score,
+        });
+        None
+    }
+}
@@ -1,22 +1,31 @@
-extern crate fixedbitset;
-extern crate num_integer;
-use self::num_integer::Integer;
-use self::fixedbitset::FixedBitSet;
+//! Square tiles of cells, in several representations, and evolving them.
+
+use fixedbitset::FixedBitSet;
+use num_integer::Integer;
+use std::cell::RefCell;
 use std::marker::{Send, Sized, Sync};
 
+/// Read only access to a square tile of cells, for example a player's design.
 pub trait LifeTileSrc {
+    /// Width and height of the tile, in cells.
     fn size(&self) -> usize;
+    /// Is the cell at (x, y) alive?
     fn get(&self, x: usize, y: usize) -> bool;
 }
 
+/// A tile which can be simulated: the unit the Life Struggle world is built from.
+/// Implementations differ only in how cells are stored (see VecTile and BitTile).
 pub trait LifeTile: LifeTileSrc
 where
     Self: Sized + Clone + Eq + Send + Sync,
 {
+    /// A size x size tile of dead cells.
     fn new(size: usize) -> Self;
+    /// Make the cell at (x, y) alive or dead.
     fn set(&mut self, x: usize, y: usize, value: bool);
 
-    fn copy_from(t_in: &LifeTileSrc) -> Self {
+    /// A copy of any tile, in this representation.
+    fn copy_from(t_in: &dyn LifeTileSrc) -> Self {
         let size = t_in.size();
         let mut t = Self::new(size);
 
@@ -25,10 +34,10 @@ where
                 t.set(x, y, t_in.get(x, y));
             }
         }
-        return t;
+        t
     }
 
-    // Mirror over y == size/2
+    /// Mirror over x == size/2, so patterns heading +x head -x instead.
     fn mirror(&self) -> Self {
         let size = self.size();
         let mut t = Self::new(size);
@@ -38,9 +47,10 @@ where
                 t.set(x, y, self.get(size - x - 1, y));
             }
         }
-        return t;
+        t
     }
 
+    /// Mirror over y == size/2, so patterns heading +y head -y instead.
     fn mirror_over_x(&self) -> Self {
         let size = self.size();
         let mut t = Self::new(size);
@@ -50,32 +60,74 @@ where
                 t.set(x, y, self.get(x, size - y - 1));
             }
         }
-        return t;
+        t
     }
 
-    // LifeTile is for use in a world where each row (along Y) of tiles is the same,
-    // so we just need 3 tiles (instead of 9) to have a complete Moore neighborhood
-    // for each cell in self.
-    fn next_generation(&self, previous: &Self, next: &Self) -> Self {
-        let size = self.size();
+    /// Number of generations until self, evolved alone (tiled with itself),
+    /// returns to its current state, or None if it doesn't within max_period.
+    fn self_period(&self, max_period: usize) -> Option<usize> {
+        let mut t = self.next_generation(self, self);
+        for period in 1..=max_period {
+            if &t == self {
+                return Some(period);
+            }
+            t = t.next_generation(&t, &t);
+        }
+        None
+    }
 
-        // Write next generation into new tile
+    /// Shift cells by dy along y, wrapping around.
+    /// Since tiles repeat endlessly along y, this is the same pattern with a different offset.
+    fn shift_y(&self, dy: usize) -> Self {
+        let size = self.size();
         let mut t = Self::new(size);
 
+        for x in 0..size {
+            for y in 0..size {
+                t.set(x, (y + dy) % size, self.get(x, y));
+            }
+        }
+        t
+    }
+
+    /// LifeTile is for use in a world where each row (along Y) of tiles is the same,
+    /// so we just need 3 tiles (instead of 9) to have a complete Moore neighborhood
+    /// for each cell in self.
+    fn next_generation(&self, previous: &Self, next: &Self) -> Self {
+        let mut t = Self::new(self.size());
+        self.next_generation_into(previous, next, &mut t);
+        t
+    }
+
+    /// Like next_generation, but overwrites every cell of out instead of allocating a new tile,
+    /// so long running loops can reuse buffers. out must be the same size as self.
+    fn next_generation_into(&self, previous: &Self, next: &Self, out: &mut Self) {
+        let size = self.size();
+        debug_assert_eq!(out.size(), size);
+
         // Do edges with general logic
         for y in [0, size - 1].iter() {
             for x in 0..size {
-                t.set(x, *y, self.next_generation_cell(previous, next, x, *y));
+                out.set(x, *y, self.next_generation_cell(previous, next, x, *y));
             }
         }
 
         for x in [0, size - 1].iter() {
             for y in 1..(size - 1) {
-                t.set(*x, y, self.next_generation_cell(previous, next, *x, y));
+                out.set(*x, y, self.next_generation_cell(previous, next, *x, y));
             }
         }
 
-        // Do center woth optimized logic
+        self.next_generation_center(out);
+    }
+
+    /// Write the next generation of all cells not on the edge of self into t,
+    /// overwriting what was there.
+    /// These only depend on self, so can use optimized logic.
+    #[allow(clippy::identity_op)]
+    fn next_generation_center(&self, t: &mut Self) {
+        let size = self.size();
+
         for y in 1..(size - 1) {
             for x in 1..(size - 1) {
                 // Count live cells in Moore neighborhood of (x,y)
@@ -83,7 +135,7 @@ where
                 let mut c1 = 0;
                 let mut c2 = 0;
                 let mut c3 = 0;
-                let mut at = |cc: &mut usize, ix: usize, iy: usize| {
+                let at = |cc: &mut usize, ix: usize, iy: usize| {
                     if self.get(ix, iy) {
                         *cc += 1;
                     };
@@ -107,18 +159,77 @@ where
                     c == 3
                 };
 
-                if v {
-                    t.set(x, y, v);
+                t.set(x, y, v);
+            }
+        }
+    }
+
+    /// For use in a world where rows (along Y) of tiles can differ,
+    /// so all 9 tiles of the Moore neighborhood of self are needed.
+    /// neighbors[dy][dx] is the tile offset by (dx - 1, dy - 1) from self: neighbors[1][1] is self.
+    fn next_generation_2d(&self, neighbors: &[[&Self; 3]; 3]) -> Self {
+        let size = self.size();
+
+        // Write next generation into new tile
+        let mut t = Self::new(size);
+
+        // Do edges with general logic
+        for y in [0, size - 1].iter() {
+            for x in 0..size {
+                t.set(x, *y, self.next_generation_cell_2d(neighbors, x, *y));
+            }
+        }
+
+        for x in [0, size - 1].iter() {
+            for y in 1..(size - 1) {
+                t.set(*x, y, self.next_generation_cell_2d(neighbors, *x, y));
+            }
+        }
+
+        self.next_generation_center(&mut t);
+
+        t
+    }
+
+    /// Next state of the cell at (x, y), given the 3 x 3 block of tiles around it, with self in the middle.
+    fn next_generation_cell_2d(&self, neighbors: &[[&Self; 3]; 3], x: usize, y: usize) -> bool {
+        let size = self.size() as isize;
+
+        let at = |x: isize, y: isize| {
+            let side = |v: isize| {
+                if v < 0 {
+                    0
+                } else if v >= size {
+                    2
+                } else {
+                    1
+                }
+            };
+            let t = neighbors[side(y)][side(x)];
+            t.get(x.mod_floor(&size) as usize, y.mod_floor(&size) as usize)
+        };
+
+        // Count live cells in Moore neighborhood of (x,y)
+        let mut c = 0;
+        for yy in -1isize..=1 {
+            for xx in -1isize..=1 {
+                if at(xx + x as isize, yy + y as isize) {
+                    c += 1;
                 }
             }
         }
 
-        return t;
+        // Apply Conway's Game of Life life and death rules
+        if self.get(x, y) {
+            c == 3 || c == 4
+        } else {
+            c == 3
+        }
     }
 
-    // LifeTile is for use in a world where each row (along Y) of tiles is the same,
-    // so we just need 3 tiles (instead of 9) to have a complete Moore neighborhood
-    // for each cell in self.
+    /// LifeTile is for use in a world where each row (along Y) of tiles is the same,
+    /// so we just need 3 tiles (instead of 9) to have a complete Moore neighborhood
+    /// for each cell in self.
     fn next_generation_cell(&self, previous: &Self, next: &Self, x: usize, y: usize) -> bool {
         let size = self.size();
 
@@ -133,7 +244,7 @@ where
                 &self
             };
 
-            return t.get(x2 as usize, y2 as usize);
+            t.get(x2 as usize, y2 as usize)
         };
 
         // Count live cells in Moore neighborhood of (x,y)
@@ -154,6 +265,7 @@ where
         }
     }
 
+    /// Print row y to stdout, as X (alive) and . (dead), without a newline.
     fn print_line(&self, y: usize) {
         for x in 0..self.size() {
             let s = if self.get(x, y) { "X" } else { "." };
@@ -161,6 +273,7 @@ where
         }
     }
 
+    /// Print the tile to stdout, one row per line.
     fn print(&self) {
         for y in 0..self.size() {
             self.print_line(y);
@@ -169,8 +282,10 @@ where
     }
 }
 
+/// Tile storing one bool per cell.
 #[derive(Debug, Clone, Eq)]
 pub struct VecTile {
+    /// Width and height, in cells.
     pub size: usize,
     cells: Vec<bool>,
 }
@@ -188,7 +303,7 @@ impl LifeTileSrc for VecTile {
 impl LifeTile for VecTile {
     fn new(size: usize) -> VecTile {
         VecTile {
-            size: size,
+            size,
             cells: vec![false; size * size],
         }
     }
@@ -211,8 +326,10 @@ impl PartialEq for VecTile {
     }
 }
 
+/// Tile storing one bit per cell.
 #[derive(Debug, Clone, Eq)]
 pub struct BitTile {
+    /// Width and height, in cells.
     pub size: usize,
     cells: FixedBitSet,
 }
@@ -230,7 +347,7 @@ impl LifeTileSrc for BitTile {
 impl LifeTile for BitTile {
     fn new(size: usize) -> BitTile {
         BitTile {
-            size: size,
+            size,
             cells: FixedBitSet::with_capacity(size * size),
         }
     }
@@ -252,3 +369,218 @@ impl PartialEq for BitTile {
         self.cells == other.cells
     }
 }
+
+thread_local! {
+    // Padded rows for ByteTile::next_generation_into, kept between calls
+    // so stepping a board doesn't allocate for every tile.
+    static PADDED_ROWS: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Tile storing one byte per cell, 0 or 1.
+/// Neighbors are counted for whole rows at once, with SIMD where available.
+#[derive(Debug, Clone, Eq)]
+pub struct ByteTile {
+    /// Width and height, in cells.
+    pub size: usize,
+    cells: Vec<u8>,
+}
+
+impl LifeTileSrc for ByteTile {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[self.index(x, y)] != 0
+    }
+}
+
+impl LifeTile for ByteTile {
+    fn new(size: usize) -> ByteTile {
+        ByteTile {
+            size,
+            cells: vec![0; size * size],
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: bool) {
+        let index = self.index(x, y);
+        self.cells[index] = value as u8;
+    }
+
+    fn next_generation_into(&self, previous: &Self, next: &Self, out: &mut Self) {
+        let size = self.size;
+        debug_assert_eq!(out.size, size);
+
+        // Pad each row with the neighboring cells from previous and next,
+        // so edge cells can use the same row logic as the rest.
+        let width = size + 2;
+        let pad = |y: usize, padded: &mut [u8]| {
+            let y = y % size;
+            padded[0] = previous.cells[previous.index(size - 1, y)];
+            padded[1..=size].copy_from_slice(&self.cells[y * size..(y + 1) * size]);
+            padded[size + 1] = next.cells[next.index(0, y)];
+        };
+        PADDED_ROWS.with(|rows| {
+            let mut rows = rows.borrow_mut();
+            rows.resize(width * 3, 0);
+            pad(size - 1, &mut rows[0..width]);
+            pad(0, &mut rows[width..2 * width]);
+
+            for y in 0..size {
+                // rows holds y - 1, y, y + 1 in turn, starting from slot (y % 3).
+                let slot = |i: usize| ((y + i) % 3) * width..((y + i) % 3 + 1) * width;
+                pad(y + 1, &mut rows[slot(2)]);
+                let row = |i: usize| &rows[slot(i)];
+                next_generation_row(
+                    row(0),
+                    row(1),
+                    row(2),
+                    &mut out.cells[y * size..(y + 1) * size],
+                );
+            }
+        });
+    }
+
+    fn next_generation_center(&self, t: &mut Self) {
+        let size = self.size;
+        if size < 3 {
+            return;
+        }
+
+        for y in 1..(size - 1) {
+            let above = &self.cells[(y - 1) * size..y * size];
+            let row = &self.cells[y * size..(y + 1) * size];
+            let below = &self.cells[(y + 1) * size..(y + 2) * size];
+            let out = &mut t.cells[y * size + 1..(y + 1) * size - 1];
+            next_generation_row(above, row, below, out);
+        }
+    }
+}
+
+impl ByteTile {
+    fn index(&self, x: usize, y: usize) -> usize {
+        x + y * self.size
+    }
+}
+
+impl PartialEq for ByteTile {
+    fn eq(&self, other: &ByteTile) -> bool {
+        self.cells == other.cells
+    }
+}
+
+// Write the next generation of cells 1..size-1 of row into out (of length size - 2),
+// given the rows above and below it.
+fn next_generation_row(above: &[u8], row: &[u8], below: &[u8], out: &mut [u8]) {
+    #[cfg(target_arch = "x86_64")]
+    let done = if is_x86_feature_detected!("avx2") {
+        unsafe { next_generation_row_avx2(above, row, below, out) }
+    } else {
+        next_generation_row_sse2(above, row, below, out)
+    };
+    #[cfg(not(target_arch = "x86_64"))]
+    let done = 0;
+
+    for x in done..out.len() {
+        let sum3 = |s: &[u8]| s[x] + s[x + 1] + s[x + 2];
+        let c = sum3(above) + sum3(row) + sum3(below);
+
+        // Apply Conway's Game of Life life and death rules; c includes the cell itself.
+        out[x] = (c == 3 || (c == 4 && row[x + 1] != 0)) as u8;
+    }
+}
+
+// 16 cells at a time. SSE2 is part of x86_64, so needs no runtime detection.
+// Returns how many cells of out were written; the rest are left for the scalar loop.
+#[cfg(target_arch = "x86_64")]
+fn next_generation_row_sse2(above: &[u8], row: &[u8], below: &[u8], out: &mut [u8]) -> usize {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 16;
+    let n = out.len() / LANES * LANES;
+    // Each 16 cell window reads up to index x + 2 + 15, which stays in bounds for x < n.
+    debug_assert!(n == 0 || n + 1 < row.len());
+    unsafe {
+        let load = |s: &[u8], i: usize| _mm_loadu_si128(s.as_ptr().add(i) as *const __m128i);
+        let three = _mm_set1_epi8(3);
+        let four = _mm_set1_epi8(4);
+        let one = _mm_set1_epi8(1);
+        let mut x = 0;
+        while x < n {
+            let mut c = _mm_setzero_si128();
+            for s in [above, row, below].iter() {
+                c = _mm_add_epi8(c, load(s, x));
+                c = _mm_add_epi8(c, load(s, x + 1));
+                c = _mm_add_epi8(c, load(s, x + 2));
+            }
+            let alive = _mm_cmpeq_epi8(load(row, x + 1), one);
+            let born = _mm_cmpeq_epi8(c, three);
+            let survives = _mm_and_si128(alive, _mm_cmpeq_epi8(c, four));
+            let v = _mm_and_si128(_mm_or_si128(born, survives), one);
+            _mm_storeu_si128(out.as_mut_ptr().add(x) as *mut __m128i, v);
+            x += LANES;
+        }
+    }
+    n
+}
+
+// As next_generation_row_sse2, but 32 cells at a time.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn next_generation_row_avx2(
+    above: &[u8],
+    row: &[u8],
+    below: &[u8],
+    out: &mut [u8],
+) -> usize {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 32;
+    let n = out.len() / LANES * LANES;
+    debug_assert!(n == 0 || n + 1 < row.len());
+    let load = |s: &[u8], i: usize| _mm256_loadu_si256(s.as_ptr().add(i) as *const __m256i);
+    let three = _mm256_set1_epi8(3);
+    let four = _mm256_set1_epi8(4);
+    let one = _mm256_set1_epi8(1);
+    let mut x = 0;
+    while x < n {
+        let mut c = _mm256_setzero_si256();
+        for s in [above, row, below].iter() {
+            c = _mm256_add_epi8(c, load(s, x));
+            c = _mm256_add_epi8(c, load(s, x + 1));
+            c = _mm256_add_epi8(c, load(s, x + 2));
+        }
+        let alive = _mm256_cmpeq_epi8(load(row, x + 1), one);
+        let born = _mm256_cmpeq_epi8(c, three);
+        let survives = _mm256_and_si256(alive, _mm256_cmpeq_epi8(c, four));
+        let v = _mm256_and_si256(_mm256_or_si256(born, survives), one);
+        _mm256_storeu_si256(out.as_mut_ptr().add(x) as *mut __m256i, v);
+        x += LANES;
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generate::Generator;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // ByteTile counts neighbors differently, so check it against VecTile,
+    // including sizes which aren't a multiple of the SIMD width.
+    #[test]
+    fn test_byte_tile_matches_vec_tile() {
+        let mut rng = StdRng::from_seed([7; 32]);
+        let random = Generator::Uniform { density: 0.4 };
+        for size in [3, 8, 17, 18, 33, 50].iter() {
+            let tiles: Vec<VecTile> = (0..3).map(|_| random.generate(*size, &mut rng)).collect();
+            let bytes: Vec<ByteTile> = tiles.iter().map(|t| ByteTile::copy_from(t)).collect();
+
+            let expected = tiles[1].next_generation(&tiles[0], &tiles[2]);
+            let actual = bytes[1].next_generation(&bytes[0], &bytes[2]);
+            assert_eq!(VecTile::copy_from(&actual), expected);
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Tournament formats: round robin, Swiss, knockout and groups.
+
+use hall_of_fame::Record;
+use std::cmp::Ordering;
+
+/// Plays a match between players a and b (indexes into the tournament's players),
+/// returning their scores, a's first, or None for a convergence draw.
+pub type Play<'a> = dyn FnMut(usize, usize) -> Option<(isize, isize)> + 'a;
+
+/// A player's results in a tournament.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    /// Index of the player.
+    pub player: usize,
+    /// Wins, losses and draws in matches played.
+    pub record: Record,
+    /// Rounds without an opponent, which count as wins in points but not in record.
+    pub byes: usize,
+    /// 2 per win or bye, 1 per draw.
+    pub points: usize,
+    /// Tie-breaker: total points of every opponent played, so points against strong players count more.
+    pub buchholz: usize,
+    /// How far the player got, in formats with stages (higher is better): ranks before points.
+    pub stage: usize,
+    /// Opponents played, in order.
+    pub opponents: Vec<usize>,
+}
+
+impl Standing {
+    fn new(player: usize) -> Standing {
+        Standing {
+            player,
+            record: Record::default(),
+            byes: 0,
+            points: 0,
+            buchholz: 0,
+            stage: 0,
+            opponents: vec![],
+        }
+    }
+
+    /// Order best first: by stage, then points, then the tie-breakers buchholz,
+    /// point difference and wins, then lowest index (the better seed).
+    pub fn compare(&self, other: &Standing) -> Ordering {
+        other
+            .stage
+            .cmp(&self.stage)
+            .then(other.points.cmp(&self.points))
+            .then(other.buchholz.cmp(&self.buchholz))
+            .then(
+                other
+                    .record
+                    .point_difference
+                    .cmp(&self.record.point_difference),
+            )
+            .then(other.record.wins.cmp(&self.record.wins))
+            .then(self.player.cmp(&other.player))
+    }
+}
+
+/// A way of ranking players by playing matches between them.
+pub trait Tournament {
+    /// Name on the command line and in reports, as parsed by parse.
+    fn name(&self) -> String;
+
+    /// Rank players 0..players, given in seeding order (best first),
+    /// playing matches with play. Returns standings best first.
+    fn run(&self, players: usize, play: &mut Play) -> Vec<Standing>;
+}
+
+// Standings while a tournament is running, indexed by player.
+struct Table {
+    standings: Vec<Standing>,
+}
+
+impl Table {
+    fn new(players: usize) -> Table {
+        Table {
+            standings: (0..players).map(Standing::new).collect(),
+        }
+    }
+
+    // Play a and b, recording the result. Returns the winner, or None for a draw.
+    fn play(&mut self, a: usize, b: usize, play: &mut Play) -> Option<usize> {
+        let score = play(a, b);
+        let (sa, sb) = score.unwrap_or((0, 0));
+        self.standings[a].record.add(score);
+        self.standings[b].record.add(score.map(|(sa, sb)| (sb, sa)));
+        self.standings[a].opponents.push(b);
+        self.standings[b].opponents.push(a);
+        match sa.cmp(&sb) {
+            Ordering::Greater => {
+                self.standings[a].points += 2;
+                Some(a)
+            }
+            Ordering::Less => {
+                self.standings[b].points += 2;
+                Some(b)
+            }
+            Ordering::Equal => {
+                self.standings[a].points += 1;
+                self.standings[b].points += 1;
+                None
+            }
+        }
+    }
+
+    fn bye(&mut self, player: usize) {
+        self.standings[player].byes += 1;
+        self.standings[player].points += 2;
+    }
+
+    fn update_buchholz(&mut self) {
+        let points: Vec<usize> = self.standings.iter().map(|s| s.points).collect();
+        for s in &mut self.standings {
+            s.buchholz = s.opponents.iter().map(|&o| points[o]).sum();
+        }
+    }
+
+    // Players ranked best first.
+    fn ranked(&mut self) -> Vec<Standing> {
+        self.update_buchholz();
+        let mut ranked = self.standings.clone();
+        ranked.sort_by(|a, b| a.compare(b));
+        ranked
+    }
+}
+
+/// Every player plays every other player once: n(n - 1)/2 matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundRobin;
+
+impl Tournament for RoundRobin {
+    fn name(&self) -> String {
+        "round-robin".to_string()
+    }
+
+    fn run(&self, players: usize, play: &mut Play) -> Vec<Standing> {
+        let mut table = Table::new(players);
+        for a in 0..players {
+            for b in (a + 1)..players {
+                table.play(a, b, play);
+            }
+        }
+        table.ranked()
+    }
+}
+
+/// Round robin within groups, with players dealt into groups by seed
+/// (1st to group 0, 2nd to group 1, ... wrapping around), so groups are similarly strong.
+/// Ranks every group's winner first, then every group's runner up, and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Groups {
+    /// Number of groups.
+    pub groups: usize,
+}
+
+impl Tournament for Groups {
+    fn name(&self) -> String {
+        format!("groups:{}", self.groups)
+    }
+
+    fn run(&self, players: usize, play: &mut Play) -> Vec<Standing> {
+        let groups = self.groups.max(1);
+        let mut table = Table::new(players);
+        let members: Vec<Vec<usize>> = (0..groups)
+            .map(|g| (g..players).step_by(groups).collect())
+            .collect();
+        for group in &members {
+            for (i, &a) in group.iter().enumerate() {
+                for &b in &group[i + 1..] {
+                    table.play(a, b, play);
+                }
+            }
+        }
+
+        table.update_buchholz();
+        let largest = members.iter().map(|g| g.len()).max().unwrap_or(0);
+        for group in &members {
+            let mut ranked: Vec<Standing> =
+                group.iter().map(|&p| table.standings[p].clone()).collect();
+            ranked.sort_by(|a, b| a.compare(b));
+            for (position, s) in ranked.iter().enumerate() {
+                table.standings[s.player].stage = largest - position;
+            }
+        }
+        table.ranked()
+    }
+}
+
+/// Swiss system: each round, players are paired with the nearest player in the current standings
+/// they haven't played yet, so after a few rounds players meet others of similar strength.
+/// With an odd number of players, the lowest ranked player without one gets a bye.
+/// Ranks n players in rounds * n/2 matches; rounds around log2(n) is usual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swiss {
+    /// Number of rounds.
+    pub rounds: usize,
+}
+
+impl Tournament for Swiss {
+    fn name(&self) -> String {
+        format!("swiss:{}", self.rounds)
+    }
+
+    fn run(&self, players: usize, play: &mut Play) -> Vec<Standing> {
+        let mut table = Table::new(players);
+        for _ in 0..self.rounds {
+            let mut order: Vec<usize> = table.ranked().iter().map(|s| s.player).collect();
+            if order.len() % 2 == 1 {
+                let bye = (0..order.len())
+                    .rev()
+                    .find(|&i| table.standings[order[i]].byes == 0)
+                    .unwrap_or(order.len() - 1);
+                table.bye(order.remove(bye));
+            }
+            while !order.is_empty() {
+                let a = order.remove(0);
+                // Avoid rematches if possible.
+                let b = order
+                    .iter()
+                    .position(|b| !table.standings[a].opponents.contains(b))
+                    .unwrap_or(0);
+                let b = order.remove(b);
+                table.play(a, b, play);
+            }
+        }
+        table.ranked()
+    }
+}
+
+/// Elimination: players are knocked out after losing lives matches
+/// (1 for single elimination, 2 for double), playing others with the same number of losses.
+/// Draws go to the better seed, so every match knocks someone closer to elimination.
+/// Players are ranked by the round they were knocked out in; the last one left wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Knockout {
+    /// Losses before a player is knocked out.
+    pub lives: usize,
+}
+
+impl Tournament for Knockout {
+    fn name(&self) -> String {
+        match self.lives {
+            1 => "knockout".to_string(),
+            2 => "double-elimination".to_string(),
+            lives => format!("knockout:{}", lives),
+        }
+    }
+
+    fn run(&self, players: usize, play: &mut Play) -> Vec<Standing> {
+        let lives = self.lives.max(1);
+        let mut table = Table::new(players);
+        let mut losses = vec![0; players];
+        let mut round = 0;
+        loop {
+            // Best seeds first within each bracket (number of losses).
+            let mut alive: Vec<usize> = (0..players).filter(|&p| losses[p] < lives).collect();
+            if alive.len() < 2 {
+                break;
+            }
+            round += 1;
+            alive.sort_by_key(|&p| losses[p]);
+
+            // Pair the best seed with the worst within each bracket,
+            // an odd player out moving down to the next bracket, or getting a bye from the last.
+            let mut pairs = vec![];
+            let mut carried: Option<usize> = None;
+            for bracket in 0..lives {
+                let mut members: Vec<usize> = carried.take().into_iter().collect();
+                members.extend(alive.iter().filter(|&&p| losses[p] == bracket));
+                if members.len() % 2 == 1 {
+                    carried = members.pop();
+                }
+                let half = members.len() / 2;
+                for i in 0..half {
+                    pairs.push((members[i], members[members.len() - 1 - i]));
+                }
+            }
+            if let Some(p) = carried {
+                table.bye(p);
+            }
+
+            for (a, b) in pairs {
+                let winner = table.play(a, b, play).unwrap_or_else(|| a.min(b));
+                let loser = if winner == a { b } else { a };
+                losses[loser] += 1;
+                if losses[loser] == lives {
+                    table.standings[loser].stage = round;
+                }
+            }
+        }
+        for (s, &l) in table.standings.iter_mut().zip(losses.iter()) {
+            if l < lives {
+                s.stage = round + 1;
+            }
+        }
+        table.ranked()
+    }
+}
+
+/// The tournament format named name: round-robin, groups:<groups>, swiss:<rounds>,
+/// knockout or double-elimination.
+pub fn parse(name: &str) -> Result<Box<dyn Tournament>, String> {
+    let mut parts = name.splitn(2, ':');
+    let format = parts.next().unwrap_or("");
+    let count = parts.next().map(|c| {
+        c.parse::<usize>()
+            .map_err(|_| format!("bad number in tournament format: {}", name))
+    });
+    match (format, count) {
+        ("round-robin", None) => Ok(Box::new(RoundRobin)),
+        ("knockout", None) => Ok(Box::new(Knockout { lives: 1 })),
+        ("double-elimination", None) => Ok(Box::new(Knockout { lives: 2 })),
+        ("groups", Some(groups)) => match groups? {
+            0 => Err(format!("tournament format needs at least 1 group: {}", name)),
+            groups => Ok(Box::new(Groups { groups })),
+        },
+        ("swiss", Some(rounds)) => Ok(Box::new(Swiss { rounds: rounds? })),
+        _ => Err(format!(
+            "unknown tournament format: {} (expected round-robin, groups:<groups>, swiss:<rounds>, knockout or double-elimination)",
+            name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Matches counted in matches, with the lower index (the stronger player) winning
+    // by the difference in index.
+    fn run(tournament: &dyn Tournament, players: usize, matches: &mut usize) -> Vec<Standing> {
+        let mut play = |a: usize, b: usize| {
+            assert!(a != b);
+            *matches += 1;
+            Some((b as isize, a as isize))
+        };
+        tournament.run(players, &mut play)
+    }
+
+    fn ranking(standings: &[Standing]) -> Vec<usize> {
+        standings.iter().map(|s| s.player).collect()
+    }
+
+    #[test]
+    fn test_formats_rank_strongest_first() {
+        let mut matches = 0;
+        let s = run(&RoundRobin, 6, &mut matches);
+        assert_eq!(matches, 15);
+        assert_eq!(ranking(&s), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(s[0].points, 10);
+
+        let mut matches = 0;
+        let s = run(&Groups { groups: 2 }, 6, &mut matches);
+        assert_eq!(matches, 6);
+        assert_eq!(ranking(&s), vec![0, 1, 2, 3, 4, 5]);
+
+        let mut matches = 0;
+        let s = run(&Swiss { rounds: 3 }, 16, &mut matches);
+        assert_eq!(matches, 24);
+        assert_eq!(s[0].player, 0);
+        assert_eq!(s[0].points, 6);
+        for st in &s {
+            let mut opponents = st.opponents.clone();
+            opponents.sort();
+            opponents.dedup();
+            assert_eq!(opponents.len(), 3, "no rematches");
+        }
+
+        let mut matches = 0;
+        let s = run(&Knockout { lives: 1 }, 8, &mut matches);
+        assert_eq!(matches, 7);
+        assert_eq!(s[0].player, 0);
+        assert_eq!(s[0].stage, 4);
+        assert_eq!(s[0].record.wins, 3);
+
+        let mut matches = 0;
+        let s = run(&Knockout { lives: 2 }, 8, &mut matches);
+        assert_eq!(s[0].player, 0);
+        assert_eq!(s[0].record.losses, 0);
+        assert!((14..=15).contains(&matches));
+    }
+
+    #[test]
+    fn test_byes_and_draws() {
+        // Odd players: someone sits out each round.
+        let mut matches = 0;
+        let s = run(&Swiss { rounds: 2 }, 5, &mut matches);
+        assert_eq!(matches, 4);
+        assert_eq!(s.iter().map(|s| s.byes).sum::<usize>(), 2);
+        assert!(s.iter().all(|s| s.byes <= 1));
+
+        let mut matches = 0;
+        let s = run(&Knockout { lives: 1 }, 5, &mut matches);
+        assert_eq!(matches, 4);
+        assert_eq!(s[0].player, 0);
+
+        // All draws: seeds decide knockouts, and round robin ties are broken by seed.
+        let mut play = |_: usize, _: usize| None;
+        let s = Knockout { lives: 1 }.run(4, &mut play);
+        assert_eq!(ranking(&s)[..2], [0, 1]);
+        let s = RoundRobin.run(4, &mut play);
+        assert!(s.iter().all(|s| s.points == 3 && s.record.draws == 3));
+        assert_eq!(ranking(&s), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_parse() {
+        for name in [
+            "round-robin",
+            "groups:4",
+            "swiss:5",
+            "knockout",
+            "double-elimination",
+        ]
+        .iter()
+        {
+            assert_eq!(parse(name).unwrap().name(), *name);
+        }
+        assert!(parse("swiss").is_err());
+        assert!(parse("swiss:x").is_err());
+        assert!(parse("ladder").is_err());
+        assert!(parse("groups:0").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+// Properties which should hold for any tiles, checked on random ones with proptest.
+// Only Conway's rule (B3/S23) is supported, so that is the only rule covered.
+extern crate life_struggle;
+extern crate proptest;
+
+use life_struggle::game;
+use life_struggle::{BitTile, Board, ByteTile, LifeTile, LifeTileSrc, VecBoard, VecTile};
+use proptest::prelude::*;
+
+// Three random tiles of the same size: previous, self and next.
+fn neighborhood() -> impl Strategy<Value = (VecTile, VecTile, VecTile)> {
+    (3usize..=12).prop_flat_map(|size| {
+        proptest::collection::vec(any::<bool>(), 3 * size * size).prop_map(move |cells| {
+            let mut tiles = [VecTile::new(size), VecTile::new(size), VecTile::new(size)];
+            for (i, c) in cells.iter().enumerate() {
+                let cell = i % (size * size);
+                tiles[i / (size * size)].set(cell % size, cell / size, *c);
+            }
+            (tiles[0].clone(), tiles[1].clone(), tiles[2].clone())
+        })
+    })
+}
+
+// Two random tiles of the same size.
+fn pair() -> impl Strategy<Value = (VecTile, VecTile)> {
+    neighborhood().prop_map(|(a, b, _)| (a, b))
+}
+
+// Naive Life on previous, t and next laid out side by side on a torus 3 tiles wide,
+// returning the middle tile: since the torus only wraps beyond previous and next,
+// that is exactly t's next generation between them.
+fn reference_next_generation(previous: &VecTile, t: &VecTile, next: &VecTile) -> VecTile {
+    let size = t.size();
+    let width = size * 3;
+    let get = |x: usize, y: usize| {
+        let tile = [previous, t, next][x / size];
+        tile.get(x % size, y)
+    };
+    let mut out = VecTile::new(size);
+    for y in 0..size {
+        for x in size..(2 * size) {
+            let mut c = 0;
+            for dy in [size - 1, 0, 1].iter() {
+                for dx in [width - 1, 0, 1].iter() {
+                    if (*dx, *dy) != (0, 0) && get((x + dx) % width, (y + dy) % size) {
+                        c += 1;
+                    }
+                }
+            }
+            let alive = if get(x, y) { c == 2 || c == 3 } else { c == 3 };
+            out.set(x - size, y, alive);
+        }
+    }
+    out
+}
+
+fn next_generation_matches_reference<T: LifeTile>(p: &VecTile, t: &VecTile, n: &VecTile) {
+    let expected = reference_next_generation(p, t, n);
+    let actual = T::copy_from(t).next_generation(&T::copy_from(p), &T::copy_from(n));
+    assert_eq!(VecTile::copy_from(&actual), expected);
+}
+
+// The invariants VecBoard's representation relies on, checked every generation.
+fn check_board_invariants<T: LifeTile>(a: &VecTile, b: &VecTile, generations: usize) {
+    let mut board = match VecBoard::new(T::copy_from(a), T::copy_from(b).mirror()) {
+        Some(board) => board,
+        None => return,
+    };
+    for g in 0..generations {
+        // Check the stored layout, since tile_at fills in the backgrounds outside it.
+        let s = board.snapshot(g);
+        let num_a = s.num_a_at_start as usize;
+        assert!(num_a <= s.tiles.len());
+        assert!(s.tiles[..num_a].iter().all(|t| *t == s.a));
+        if num_a < s.tiles.len() {
+            assert!(s.tiles[num_a] != s.a);
+            assert!(*s.tiles.last().unwrap() != s.b);
+        }
+        assert!(s.a != s.b);
+        if !board.step() {
+            break;
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn next_generation_matches_reference_torus((p, t, n) in neighborhood()) {
+        next_generation_matches_reference::<VecTile>(&p, &t, &n);
+        next_generation_matches_reference::<BitTile>(&p, &t, &n);
+        next_generation_matches_reference::<ByteTile>(&p, &t, &n);
+    }
+
+    #[test]
+    fn mirror_commutes_with_evolution((p, t, n) in neighborhood()) {
+        // Mirroring over x swaps which side previous and next are on.
+        prop_assert_eq!(
+            t.mirror().next_generation(&n.mirror(), &p.mirror()),
+            t.next_generation(&p, &n).mirror()
+        );
+        prop_assert_eq!(
+            t.mirror_over_x().next_generation(&p.mirror_over_x(), &n.mirror_over_x()),
+            t.next_generation(&p, &n).mirror_over_x()
+        );
+    }
+}
+
+proptest! {
+    // Whole struggles are slow, so check fewer cases.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn struggle_is_symmetric((a, b) in pair()) {
+        // Swapping the players gives the mirror image of the same world.
+        let score = |a: &VecTile, b: &VecTile| game::struggle_board(100, a, b).map(|x| x.score());
+        let ab = score(&a, &b);
+        let ba = score(&b, &a);
+        prop_assert_eq!(ab, ba.map(|(s_b, s_a)| (s_a, s_b)));
+    }
+
+    #[test]
+    fn board_invariants_hold((a, b) in pair()) {
+        check_board_invariants::<VecTile>(&a, &b, 100);
+        check_board_invariants::<ByteTile>(&a, &b, 100);
+    }
+}